@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use pyo3::prelude::{pyfunction, pymodule, PyModule, PyResult};
+use pyo3::{
+    exceptions, wrap_pyfunction, wrap_pymodule, ObjectProtocol, PyAny, PyErr, PyRef, Python,
+};
+
+use crate::primitive_vector::{BoolVector, F64Vector, I64Vector};
+use crate::rrb_vector::RrbVector;
+use crate::{List, Map, Set, Vector};
+
+/// The level of every node of a container by address, the root being on level 1.
+pub type Nodes = HashMap<usize, usize>;
+
+/// Implemented by the containers whose nodes belong to this crate, to hand them to `pyrpds.debug`.
+pub trait Layout {
+    fn nodes(&self) -> Nodes;
+}
+
+/// Walks the nodes of a container. rpds keeps the nodes of its vectors, lists and hash tries
+/// private, so the containers built on them raise `NotImplementedError` rather than guessing.
+fn nodes(object: &PyAny) -> PyResult<Nodes> {
+    if let Ok(container) = object.extract::<PyRef<RrbVector>>() {
+        return Ok(container.nodes());
+    }
+
+    let rpds_backed = object.extract::<PyRef<Vector>>().is_ok()
+        || object.extract::<PyRef<List>>().is_ok()
+        || object.extract::<PyRef<Map>>().is_ok()
+        || object.extract::<PyRef<Set>>().is_ok()
+        || object.extract::<PyRef<I64Vector>>().is_ok()
+        || object.extract::<PyRef<F64Vector>>().is_ok()
+        || object.extract::<PyRef<BoolVector>>().is_ok();
+    if rpds_backed {
+        Err(PyErr::new::<exceptions::NotImplementedError, _>(format!(
+            "The nodes of a {} are private to rpds!",
+            object.get_type().name()
+        )))
+    } else {
+        Err(PyErr::new::<exceptions::TypeError, _>(format!(
+            "Expected a persistent vector, list, map or set, got {}!",
+            object.get_type().name()
+        )))
+    }
+}
+
+/// Tells whether two containers share nodes, i.e. whether one of them was derived from the other,
+/// or both from a common ancestor, without copying everything. See `nodes` for the containers
+/// which can be walked.
+#[pyfunction]
+fn shares_structure(a: &PyAny, b: &PyAny) -> PyResult<bool> {
+    let (a, b) = (nodes(a)?, nodes(b)?);
+    Ok(a.keys().any(|address| b.contains_key(address)))
+}
+
+/// Returns the fraction of the nodes of `a` which `b` shares.
+#[pyfunction]
+fn shared_fraction(a: &PyAny, b: &PyAny) -> PyResult<f64> {
+    let (a, b) = (nodes(a)?, nodes(b)?);
+    let shared = a.keys().filter(|address| b.contains_key(address)).count();
+
+    // A container always has a root.
+    #[allow(clippy::cast_precision_loss)]
+    Ok(shared as f64 / a.len() as f64)
+}
+
+/// Returns the number of distinct nodes of a container, a node shared by several branches is
+/// counted once.
+#[pyfunction]
+fn node_count(x: &PyAny) -> PyResult<usize> {
+    Ok(nodes(x)?.len())
+}
+
+/// Returns the number of nodes on the longest path from the root of a container to a leaf,
+/// 1 when the root is a leaf.
+#[pyfunction]
+fn depth(x: &PyAny) -> PyResult<usize> {
+    Ok(nodes(x)?.values().copied().max().unwrap_or(0))
+}
+
+#[pymodule]
+fn debug(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pyfunction!(shares_structure))?;
+    m.add_wrapped(wrap_pyfunction!(shared_fraction))?;
+    m.add_wrapped(wrap_pyfunction!(node_count))?;
+    m.add_wrapped(wrap_pyfunction!(depth))?;
+
+    Ok(())
+}
+
+pub fn py_binding(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pymodule!(debug))?;
+    // Lets `import pyrpds.debug` find the submodule, as if `pyrpds` was a package.
+    py.import("sys")?
+        .get("modules")?
+        .set_item("pyrpds.debug", m.getattr("debug")?)?;
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::hash::{Hash, Hasher};
+
+use pyo3::PyResult;
+
+use crate::object::catch;
+
+#[cfg(target_pointer_width = "64")]
+mod tuple_constants {
+    pub const PRIME_1: usize = 11_400_714_785_074_694_791;
+    pub const PRIME_2: usize = 14_029_467_366_897_019_727;
+    pub const PRIME_5: usize = 2_870_177_450_012_600_261;
+    pub const ROTATE: u32 = 31;
+}
+
+#[cfg(not(target_pointer_width = "64"))]
+mod tuple_constants {
+    pub const PRIME_1: usize = 2_654_435_761;
+    pub const PRIME_2: usize = 2_246_822_519;
+    pub const PRIME_5: usize = 374_761_393;
+    pub const ROTATE: u32 = 13;
+}
+
+/// Hashes a value through its `Hash` implementation, which depends on the order of the elements.
+/// Raises the exception of any element whose `__hash__` fails.
+#[allow(clippy::cast_possible_wrap)]
+pub fn ordered_hash<T: Hash>(value: &T) -> PyResult<isize> {
+    let hash = catch(|| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    })?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(hash as isize)
+}
+
+/// Combines the hashes of the elements of a tuple, the way Python (3.8 and newer) does.
+#[allow(clippy::cast_possible_wrap)]
+#[allow(clippy::cast_sign_loss)]
+#[must_use]
+pub fn tuple_hash(hashes: &[isize]) -> isize {
+    let mut hash = tuple_constants::PRIME_5;
+    for element_hash in hashes {
+        hash = hash.wrapping_add((*element_hash as usize).wrapping_mul(tuple_constants::PRIME_2));
+        hash = hash.rotate_left(tuple_constants::ROTATE);
+        hash = hash.wrapping_mul(tuple_constants::PRIME_1);
+    }
+    hash = hash.wrapping_add(hashes.len() ^ (tuple_constants::PRIME_5 ^ 0x0035_D373));
+
+    if hash == usize::MAX {
+        return 1_546_275_796;
+    }
+    hash as isize
+}
+
+fn shuffle_bits(hash: usize) -> usize {
+    ((hash ^ 0x055B_4DB3) ^ (hash << 16)).wrapping_mul(3_644_798_167)
+}
+
+/// Combines the hashes of the elements of an unordered collection into the state from which
+/// Python computes the hash of a `frozenset`, see `frozenset_finish`. Since xor is commutative,
+/// the state doesn't depend on the iteration order.
+pub fn frozenset_state<I: IntoIterator<Item = isize>>(hashes: I) -> usize {
+    hashes.into_iter().fold(0, frozenset_toggle)
+}
+
+/// Adds an element to, or removes it from, a `frozenset_state`.
+#[allow(clippy::cast_sign_loss)]
+#[must_use]
+pub fn frozenset_toggle(state: usize, element_hash: isize) -> usize {
+    state ^ shuffle_bits(element_hash as usize)
+}
+
+/// Turns the state of a collection with `length` elements into its hash.
+#[allow(clippy::cast_possible_wrap)]
+#[must_use]
+pub fn frozenset_finish(state: usize, length: usize) -> isize {
+    let mut hash = state;
+    hash ^= (length + 1).wrapping_mul(1_927_868_237);
+    hash ^= (hash >> 11) ^ (hash >> 25);
+    hash = hash.wrapping_mul(69069).wrapping_add(907_133_923);
+
+    if hash == usize::MAX {
+        return 590_923_713;
+    }
+    hash as isize
+}
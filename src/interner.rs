@@ -0,0 +1,186 @@
+use pyo3::class::PySequenceProtocol;
+use pyo3::prelude::{pyclass, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::types::PyDict;
+use pyo3::{
+    AsPyPointer, AsPyRef, ObjectProtocol, Py, PyAny, PyCell, PyNativeType, Python, ToPyObject,
+};
+
+use crate::memory::extra_bytes;
+use crate::object::gil_held;
+use crate::primitive_vector::{BoolVector, F64Vector, I64Vector};
+use crate::rrb_vector::RrbVector;
+use crate::{List, Map, Set, Vector};
+
+/// Canonicalizes equal containers to a single instance, so that the duplicates can be freed
+/// and interned values compare by identity. The containers nested in maps, vectors and sets
+/// are interned first, so equal nested containers are shared as well.
+///
+/// Only whole containers are canonicalized: the nodes inside a container are private to rpds,
+/// two containers which differ in a single element don't share the nodes they have in common
+/// unless one was derived from the other.
+///
+/// The canonical instances are kept alive until `clear` is called.
+#[pyclass]
+pub struct Interner {
+    table: Py<PyDict>,
+    hits: usize,
+    misses: usize,
+    saved_bytes: usize,
+}
+
+fn is_container(object: &PyAny) -> bool {
+    object.downcast::<PyCell<Map>>().is_ok()
+        || object.downcast::<PyCell<Set>>().is_ok()
+        || object.downcast::<PyCell<Vector>>().is_ok()
+        || object.downcast::<PyCell<RrbVector>>().is_ok()
+        || object.downcast::<PyCell<List>>().is_ok()
+        || object.downcast::<PyCell<I64Vector>>().is_ok()
+        || object.downcast::<PyCell<F64Vector>>().is_ok()
+        || object.downcast::<PyCell<BoolVector>>().is_ok()
+}
+
+fn is_same(left: &PyObject, right: &PyObject) -> bool {
+    left.as_ptr() == right.as_ptr()
+}
+
+impl Interner {
+    fn intern_object(&mut self, py: Python, object: &PyAny) -> PyResult<PyObject> {
+        if !is_container(object) {
+            return Ok(object.to_object(py));
+        }
+
+        let object = self.intern_elements(py, object)?;
+        let object_ref = object.as_ref(py);
+        // `PyDict::get_item` would swallow the error raised by an unhashable element.
+        object_ref.hash()?;
+
+        let table = self.table.as_ref(py);
+        if let Some(canonical) = table.get_item(object_ref) {
+            let canonical = canonical.to_object(py);
+            if !is_same(&canonical, &object) {
+                self.hits += 1;
+                self.saved_bytes += extra_bytes(canonical.as_ref(py), object_ref)?;
+            }
+            return Ok(canonical);
+        }
+
+        self.misses += 1;
+        table.set_item(object_ref, object_ref)?;
+        Ok(object)
+    }
+
+    /// Rebuilds the container with the canonical instances of the containers it holds.
+    fn intern_elements(&mut self, py: Python, object: &PyAny) -> PyResult<PyObject> {
+        let mut interned = object.to_object(py);
+
+        if object.downcast::<PyCell<Map>>().is_ok() {
+            for item in object.call_method0("items")?.iter()? {
+                let (key, value) = item?.extract::<(PyObject, PyObject)>()?;
+                let canonical_key = self.intern_object(py, key.as_ref(py))?;
+                let canonical_value = self.intern_object(py, value.as_ref(py))?;
+                if !is_same(&canonical_key, &key) {
+                    interned = interned.call_method1(py, "discard", (key,))?;
+                } else if is_same(&canonical_value, &value) {
+                    continue;
+                }
+                interned = interned.call_method1(py, "set", (canonical_key, canonical_value))?;
+            }
+        } else if object.downcast::<PyCell<Set>>().is_ok() {
+            for element in object.iter()? {
+                let element = element?.to_object(py);
+                let canonical = self.intern_object(py, element.as_ref(py))?;
+                if !is_same(&canonical, &element) {
+                    interned = interned.call_method1(py, "discard", (element,))?;
+                    interned = interned.call_method1(py, "add", (canonical,))?;
+                }
+            }
+        } else if object.downcast::<PyCell<Vector>>().is_ok()
+            || object.downcast::<PyCell<RrbVector>>().is_ok()
+        {
+            for (index, element) in object.iter()?.enumerate() {
+                let element = element?.to_object(py);
+                let canonical = self.intern_object(py, element.as_ref(py))?;
+                if !is_same(&canonical, &element) {
+                    interned = interned.call_method1(py, "set", (index, canonical))?;
+                }
+            }
+        }
+
+        Ok(interned)
+    }
+}
+
+#[pymethods]
+impl Interner {
+    #[new]
+    fn new(py: Python) -> Self {
+        Interner {
+            table: PyDict::new(py).into(),
+            hits: 0,
+            misses: 0,
+            saved_bytes: 0,
+        }
+    }
+
+    /// Returns the canonical instance of a container equal to `object`, which becomes the
+    /// canonical one if there is none yet. Other objects are returned as they are.
+    pub fn intern(&mut self, py: Python, object: &PyAny) -> PyResult<PyObject> {
+        self.intern_object(py, object)
+    }
+
+    /// Forgets the canonical instances and resets the statistics.
+    pub fn clear(&mut self, py: Python) {
+        self.table.as_ref(py).clear();
+        self.hits = 0;
+        self.misses = 0;
+        self.saved_bytes = 0;
+    }
+
+    /// The number of containers for which a canonical instance was returned instead.
+    #[getter]
+    #[must_use]
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// The number of containers which became canonical instances.
+    #[getter]
+    #[must_use]
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// The bytes held by the duplicates replaced with canonical instances, as measured by
+    /// `memory_usage`, without the memory they already shared with them. These bytes are
+    /// freed once the duplicates aren't referenced anymore.
+    #[getter]
+    #[must_use]
+    pub fn saved_bytes(&self) -> usize {
+        self.saved_bytes
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for Interner {
+    fn __len__(&self) -> PyResult<usize> {
+        // Python calls `len()` with the GIL held, pyo3 just doesn't pass it.
+        Ok(self.table.as_ref(unsafe { gil_held() }).len())
+    }
+
+    fn __contains__(&self, object: &PyAny) -> PyResult<bool> {
+        // `PyDict::get_item` would swallow the error raised by an unhashable object.
+        object.hash()?;
+        Ok(self.table.as_ref(object.py()).get_item(object).is_some())
+    }
+}
+
+pub fn py_binding(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Interner>()?;
+
+    // `pyrpds.intern` uses a module-wide interner, `pyrpds.interner`.
+    let interner = Py::new(py, Interner::new(py))?.to_object(py);
+    m.add("intern", interner.getattr(py, "intern")?)?;
+    m.add("interner", interner)?;
+
+    Ok(())
+}
@@ -1,16 +1,174 @@
+use std::mem::ManuallyDrop;
 
 use pyo3::prelude::{pyclass, pyproto};
 use pyo3::{PyIterProtocol, PyResult, PyObject, PyRefMut, Py};
 
+use crate::object::{extract_py_object, Object};
+
+/// A vector whose elements can be looked up by index, see `VectorCursor`.
+#[allow(clippy::len_without_is_empty)]
+pub trait Indexed: 'static {
+    fn len(&self) -> usize;
+
+    /// The element at `index`, which is less than `len`.
+    fn py_element(&self, index: usize) -> PyResult<PyObject>;
+}
+
+impl Indexed for rpds::Vector<Object> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn py_element(&self, index: usize) -> PyResult<PyObject> {
+        extract_py_object(self.get(index))
+    }
+}
+
+impl Indexed for crate::rrb::Vector<Object> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn py_element(&self, index: usize) -> PyResult<PyObject> {
+        extract_py_object(self.get(index))
+    }
+}
+
+/// Walks over a clone of a vector, which is cheap to make, using indices as cursors.
+pub struct VectorCursor<V: Indexed = rpds::Vector<Object>> {
+    vector: V,
+    // The elements left to yield are those in `front..back`.
+    front: usize,
+    back: usize,
+}
+
+impl<V: Indexed> VectorCursor<V> {
+    #[must_use]
+    pub fn new(vector: V) -> Self {
+        let back = vector.len();
+        VectorCursor {
+            vector,
+            front: 0,
+            back,
+        }
+    }
+}
+
+impl<V: Indexed> Iterator for VectorCursor<V> {
+    type Item = PyResult<PyObject>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.vector.py_element(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<V: Indexed> DoubleEndedIterator for VectorCursor<V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.vector.py_element(self.back))
+    }
+}
+
+/// Walks over a clone of a `rpds::List` by dropping its head after every step.
+pub struct ListCursor {
+    list: rpds::List<Object>,
+}
+
+impl ListCursor {
+    #[must_use]
+    pub fn new(list: rpds::List<Object>) -> Self {
+        ListCursor { list }
+    }
+}
+
+impl Iterator for ListCursor {
+    type Item = PyResult<PyObject>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = extract_py_object(Some(self.list.first()?));
+        self.list.drop_first_mut();
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
+    }
+}
+
+/// Owns a clone of a persistent container together with an iterator borrowing from it.
+/// Used for the hash tries and the red-black trees, whose nodes can only be walked through
+/// their own iterators. Prefer a cursor, such as `VectorCursor`, wherever one can be written.
+pub struct OwningIterator<C: 'static, I> {
+    // Must be dropped before the container it borrows from, see `Drop` below.
+    iterator: ManuallyDrop<I>,
+    container: *mut C,
+}
+
+impl<C: 'static, I> OwningIterator<C, I> {
+    /// Moves `container` to the heap and lets `make_iterator` borrow it.
+    ///
+    /// # Safety
+    ///
+    /// The reference passed to `make_iterator` claims to be `'static`, but the container is freed
+    /// along with the `OwningIterator`. `make_iterator` must only use it to build the returned
+    /// iterator, and must not let it escape anywhere else. The items of the iterator must not
+    /// borrow from the container either, i.e. `I::Item` must own its data.
+    pub unsafe fn new<F: FnOnce(&'static C) -> I>(container: C, make_iterator: F) -> Self {
+        let container = Box::into_raw(Box::new(container));
+        // The container lives on the heap, is never mutated and is only freed after the iterator.
+        let iterator = make_iterator(&*container);
+
+        OwningIterator {
+            iterator: ManuallyDrop::new(iterator),
+            container,
+        }
+    }
+}
+
+impl<C: 'static, I: Iterator> Iterator for OwningIterator<C, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iterator.size_hint()
+    }
+}
+
+impl<C: 'static, I> Drop for OwningIterator<C, I> {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.iterator);
+            drop(Box::from_raw(self.container));
+        }
+    }
+}
+
 #[pyclass]
 pub struct PyObjectIterator {
-    iterator: std::vec::IntoIter<PyObject>
+    iterator: Box<dyn Iterator<Item = PyResult<PyObject>>>,
 }
 
 impl PyObjectIterator {
     #[must_use]
-    pub fn new(iterator: std::vec::IntoIter<PyObject>) -> Self {
-        PyObjectIterator{iterator}
+    pub fn new<I: Iterator<Item = PyResult<PyObject>> + 'static>(iterator: I) -> Self {
+        PyObjectIterator {
+            iterator: Box::new(iterator),
+        }
     }
 }
 
@@ -20,19 +178,21 @@ impl PyIterProtocol for PyObjectIterator {
         Ok(slf.into())
     }
     fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
-        Ok(slf.iterator.next())
+        slf.iterator.next().transpose()
     }
 }
 
 #[pyclass]
 pub struct PyObjectPairIterator {
-    iterator: std::vec::IntoIter<(PyObject, PyObject)>
+    iterator: Box<dyn Iterator<Item = PyResult<(PyObject, PyObject)>>>,
 }
 
 impl PyObjectPairIterator {
     #[must_use]
-    pub fn new(iterator: std::vec::IntoIter<(PyObject, PyObject)>) -> Self {
-        PyObjectPairIterator{iterator}
+    pub fn new<I: Iterator<Item = PyResult<(PyObject, PyObject)>> + 'static>(iterator: I) -> Self {
+        PyObjectPairIterator {
+            iterator: Box::new(iterator),
+        }
     }
 }
 
@@ -42,6 +202,6 @@ impl PyIterProtocol for PyObjectPairIterator {
         Ok(slf.into())
     }
     fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<(PyObject, PyObject)>> {
-        Ok(slf.iterator.next())
+        slf.iterator.next().transpose()
     }
-}
\ No newline at end of file
+}
@@ -7,10 +7,21 @@ use pyo3::prelude::{pymodule, PyModule, PyResult, Python};
 
 pub mod macros; #[rustfmt::skip]
 pub mod iterators;
+pub mod debug;
+pub mod hashing;
+pub mod interner;
 pub mod list;
 pub mod map;
+pub mod map_views;
+pub mod memory;
 pub mod object;
+pub mod ordered;
+pub mod primitive_vector;
+pub mod rrb;
+pub mod rrb_vector;
 pub mod set;
+pub mod sorted_map;
+pub mod sorted_set;
 pub mod vector;
 
 pub use crate::list::List;
@@ -21,9 +32,17 @@ pub use crate::vector::Vector;
 
 #[pymodule]
 fn pyrpds(py: Python, m: &PyModule) -> PyResult<()> {
+    debug::py_binding(py, m)?;
+    interner::py_binding(py, m)?;
     list::py_binding(py, m)?;
     map::py_binding(py, m)?;
+    map_views::py_binding(py, m)?;
+    memory::py_binding(py, m)?;
+    primitive_vector::py_binding(py, m)?;
+    rrb_vector::py_binding(py, m)?;
     set::py_binding(py, m)?;
+    sorted_map::py_binding(py, m)?;
+    sorted_set::py_binding(py, m)?;
     vector::py_binding(py, m)?;
 
     Ok(())
@@ -1,14 +1,17 @@
+use std::cell::Cell;
 use std::hash::{Hash, Hasher};
 
 use pyo3::class::{PyObjectProtocol, PySequenceProtocol};
 use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
 use pyo3::types::PyTuple;
 use pyo3::{
-    exceptions, wrap_pyfunction, ObjectProtocol, PyAny, PyCell, PyErr, PyIterProtocol, PyRefMut,
-    Python,
+    exceptions, wrap_pyfunction, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol, PyRef,
+    PyRefMut, Python,
 };
 
-use crate::object::{extract_py_object, Object};
+use crate::hashing::ordered_hash;
+use crate::memory::{cell_bytes, Footprint, MemoryUsage, ESTIMATED_LIST_NODE_BYTES};
+use crate::object::{deepcopy_object, extract_py_object, Object};
 
 type RpdsList = rpds::List<Object>;
 
@@ -16,30 +19,55 @@ type RpdsList = rpds::List<Object>;
 #[derive(Default)]
 pub struct List {
     value: RpdsList,
+    hash: Cell<Option<isize>>,
 }
 
 impl List {
     #[must_use]
     pub fn new() -> Self {
+        Self::from(RpdsList::new())
+    }
+
+    /// The hash is computed once, the elements of a list can't change.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
+        }
+
+        let hash = ordered_hash(self)?;
+        self.hash.set(Some(hash));
+        Ok(hash)
+    }
+}
+
+impl From<RpdsList> for List {
+    fn from(value: RpdsList) -> Self {
         List {
-            value: RpdsList::new(),
+            value,
+            hash: Cell::new(None),
         }
     }
 }
 
+impl Footprint for List {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        usage.add_cells(
+            self.value.iter(),
+            cell_bytes::<Object>(),
+            ESTIMATED_LIST_NODE_BYTES,
+        );
+    }
+}
+
 #[pymethods]
 impl List {
     pub fn push_front(&self, py_object: PyObject) -> PyResult<Self> {
-        let new_self = Self {
-            value: self.value.push_front(Object::new(py_object)),
-        };
+        let new_self = Self::from(self.value.push_front(Object::new(py_object)));
         Ok(new_self)
     }
 
     pub fn reverse(&self) -> PyResult<Self> {
-        let reversed = Self {
-            value: self.value.reverse(),
-        };
+        let reversed = Self::from(self.value.reverse());
         Ok(reversed)
     }
 
@@ -47,6 +75,51 @@ impl List {
     pub fn first(&self) -> PyResult<PyObject> {
         extract_py_object(self.value.first())
     }
+
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
+
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut elements = std::vec::Vec::with_capacity(slf.value.len());
+        let mut copied = false;
+        for element in slf.value.iter() {
+            match deepcopy_object(py, element, &memo)? {
+                Some(object) => {
+                    elements.push(object);
+                    copied = true;
+                }
+                None => elements.push(element.clone()),
+            }
+        }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+
+        // Lists can only grow at the front, hence the elements are pushed in reverse.
+        let mut value = RpdsList::new();
+        for element in elements.into_iter().rev() {
+            value.push_front_mut(element);
+        }
+        Py::new(py, Self::from(value))
+    }
+
+    pub fn __reversed__(&self) -> PyResult<crate::iterators::PyObjectIterator> {
+        // A singly linked list can only be walked from the front, so it is reversed once.
+        // Only the element references are copied, Python isn't involved.
+        Ok(crate::iterators::PyObjectIterator::new(
+            crate::iterators::ListCursor::new(self.value.reverse()),
+        ))
+    }
 }
 
 impl Hash for List {
@@ -71,13 +144,8 @@ impl PySequenceProtocol for List {
 #[pyproto]
 impl PyIterProtocol for List {
     fn __iter__(slf: PyRefMut<Self>) -> PyResult<crate::iterators::PyObjectIterator> {
-        let mut elements = std::vec::Vec::new();
-        for element in slf.value.iter() {
-            elements.push(extract_py_object(Some(element))?)
-        }
-
         Ok(crate::iterators::PyObjectIterator::new(
-            elements.into_iter(),
+            crate::iterators::ListCursor::new(slf.value.clone()),
         ))
     }
 }
@@ -90,34 +158,34 @@ impl std::fmt::Display for List {
     }
 }
 
+/// Pushes the elements of an iterable to the front of the list in place, one after the other.
+fn push_front_all(value: &mut RpdsList, iterable: &PyAny) -> PyResult<()> {
+    for element in iterable.iter()? {
+        value.push_front_mut(Object::new(element?.extract::<PyObject>()?));
+    }
+    Ok(())
+}
+
 #[pyfunction(args = "*")]
 fn plist(args: &PyTuple) -> PyResult<List> {
-    let mut list = List::new();
+    let mut value = RpdsList::new();
     if args.is_empty() {
-        return Ok(list);
+        return Ok(List::from(value));
     } else if args.len() > 1 {
         return Err(PyErr::new::<exceptions::ValueError, _>(
             "Incorrect number of arguments!!",
         ));
     }
 
-    let iterator = args.get_item(0).as_ref().iter().unwrap();
-    for element in iterator {
-        let element = element.unwrap().extract::<PyObject>()?;
-        list = list.push_front(element)?;
-    }
-    Ok(list)
+    push_front_all(&mut value, args.get_item(0))?;
+    Ok(List::from(value))
 }
 
 #[pyfunction(args = "*")]
 fn l(args: &PyTuple) -> PyResult<List> {
-    let mut list = List::new();
-
-    for element in args.iter() {
-        let element = element.extract::<PyObject>()?;
-        list = list.push_front(element)?;
-    }
-    Ok(list)
+    let mut value = RpdsList::new();
+    push_front_all(&mut value, args.as_ref())?;
+    Ok(List::from(value))
 }
 
 pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -5,12 +5,8 @@ macro_rules! py_object_protocol {
     ($struct_:ty) => {
         #[pyproto]
         impl PyObjectProtocol for $struct_ {
-            #[allow(clippy::cast_possible_truncation)]
-            #[allow(clippy::cast_possible_wrap)]
             fn __hash__(&self) -> PyResult<isize> {
-                let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                self.hash(&mut hasher);
-                Ok(hasher.finish() as isize)
+                self.py_hash()
             }
 
             fn __richcmp__(
@@ -29,10 +25,16 @@ macro_rules! py_object_protocol {
                     };
                 }
                 let other = other?.borrow();
+                // Comparing a value to itself, e.g. to an interned copy, takes constant time.
+                let identical = std::ptr::eq(self, &*other);
 
                 match op {
-                    pyo3::class::basic::CompareOp::Eq => Ok(self.value == other.value),
-                    pyo3::class::basic::CompareOp::Ne => Ok(self.value != other.value),
+                    pyo3::class::basic::CompareOp::Eq => {
+                        Ok(identical || $crate::object::catch(|| self.value == other.value)?)
+                    }
+                    pyo3::class::basic::CompareOp::Ne => {
+                        Ok(!identical && $crate::object::catch(|| self.value != other.value)?)
+                    }
                     _ => Err(PyErr::new::<exceptions::TypeError, _>(
                         "Invalid comparison operator!".to_string(),
                     )),
@@ -40,7 +42,7 @@ macro_rules! py_object_protocol {
             }
 
             fn __repr__(&self) -> pyo3::PyResult<String> {
-                Ok(format!("{}", self))
+                $crate::object::repr(self)
             }
         }
     };
@@ -1,14 +1,17 @@
-use std::hash::{Hash, Hasher};
+use std::cell::Cell;
 
 use pyo3::class::PyObjectProtocol;
 use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
 use pyo3::types::{PyDict, PyTuple};
 use pyo3::{
-    exceptions, wrap_pyfunction, AsPyRef, IntoPy, ObjectProtocol, Py, PyAny, PyCell, PyErr,
-    PyIterProtocol, PyMappingProtocol, PyRefMut, PySequenceProtocol, Python, ToPyObject,
+    exceptions, wrap_pyfunction, AsPyRef, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol,
+    PyMappingProtocol, PyRef, PyRefMut, PySequenceProtocol, Python, ToPyObject,
 };
 
-use crate::object::{extract_py_object, Object};
+use crate::hashing::{frozenset_finish, frozenset_state, frozenset_toggle, tuple_hash};
+use crate::map_views::{ItemsView, KeysView, ValuesView};
+use crate::memory::{cell_bytes, Footprint, MemoryUsage, ESTIMATED_HASH_TRIE_NODE_BYTES};
+use crate::object::{catch, deepcopy_object, extract_py_object, keyword_only_argument, Object};
 
 type RpdsMap = rpds::HashTrieMap<Object, Object>;
 
@@ -16,134 +19,239 @@ type RpdsMap = rpds::HashTrieMap<Object, Object>;
 #[derive(Default)]
 pub struct Map {
     value: RpdsMap,
+    // The `frozenset_state` of the items, once computed.
+    hash_state: Cell<Option<usize>>,
 }
 
 impl Map {
     #[must_use]
     pub fn new() -> Self {
+        Self::from(RpdsMap::new())
+    }
+
+    /// Hashes the map the same way as a `frozenset` of its items.
+    /// The hash is computed once and carried over to the maps derived from this one.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        Ok(frozenset_finish(self.items_state()?, self.value.size()))
+    }
+
+    fn items_state(&self) -> PyResult<usize> {
+        if let Some(state) = self.hash_state.get() {
+            return Ok(state);
+        }
+
+        let hashes = self
+            .value
+            .iter()
+            .map(|(key, value)| item_hash(key, value))
+            .collect::<PyResult<Vec<_>>>()?;
+        let state = frozenset_state(hashes);
+        self.hash_state.set(Some(state));
+        Ok(state)
+    }
+
+    /// Looks up the value currently stored under `key`, which is needed to update the cached hash.
+    fn cached_value(&self, key: &Object) -> PyResult<Option<&Object>> {
+        if self.hash_state.get().is_none() {
+            return Ok(None);
+        }
+        catch(|| self.value.get(key))
+    }
+
+    /// Derives a map from this one whose items differ by `changes` (added or removed items),
+    /// updating the cached hash. The cache is dropped if one of the items can't be hashed.
+    fn derive<'a, I: IntoIterator<Item = (&'a Object, &'a Object)>>(
+        &self,
+        value: RpdsMap,
+        changes: I,
+    ) -> Self {
+        let hash_state = self
+            .hash_state
+            .get()
+            .and_then(|state| toggle_items(state, changes));
+
         Map {
-            value: RpdsMap::new(),
+            value,
+            hash_state: Cell::new(hash_state),
         }
     }
 }
 
+/// Toggles the hashes of `changes` in a `frozenset_state`, `None` if one of them can't be hashed.
+fn toggle_items<'a, I: IntoIterator<Item = (&'a Object, &'a Object)>>(
+    state: usize,
+    changes: I,
+) -> Option<usize> {
+    changes.into_iter().try_fold(state, |state, (key, value)| {
+        item_hash(key, value)
+            .ok()
+            .map(|hash| frozenset_toggle(state, hash))
+    })
+}
+
+impl From<RpdsMap> for Map {
+    fn from(value: RpdsMap) -> Self {
+        Map {
+            value,
+            hash_state: Cell::new(None),
+        }
+    }
+}
+
+fn item_hash(key: &Object, value: &Object) -> PyResult<isize> {
+    Ok(tuple_hash(&[key.py_hash()?, value.py_hash()?]))
+}
+
+impl Footprint for Map {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        // The key and the value of an entry share a cell.
+        usage.add_cells(
+            self.value.keys(),
+            cell_bytes::<(Object, Object)>(),
+            ESTIMATED_HASH_TRIE_NODE_BYTES,
+        );
+    }
+}
+
 #[pymethods]
 impl Map {
     pub fn set(&self, py_key: PyObject, py_value: PyObject) -> PyResult<Self> {
-        let new_self = Self {
-            value: self
-                .value
-                .insert(Object::new(py_key), Object::new(py_value)),
-        };
-        Ok(new_self)
+        let key = Object::hashable(py_key)?;
+        let value = Object::new(py_value);
+
+        let old_value = self.cached_value(&key)?;
+        let new_value = catch(|| self.value.insert(key.clone(), value.clone()))?;
+
+        let removed = old_value.map(|old_value| (&key, old_value));
+        Ok(self.derive(new_value, removed.into_iter().chain(Some((&key, &value)))))
     }
+
     pub fn discard(&self, py_object: PyObject) -> PyResult<Self> {
-        let object = Object::new(py_object);
+        let object = Object::hashable(py_object)?;
 
-        let new_self = Self {
-            value: self.value.remove(&object),
-        };
+        let old_value = self.cached_value(&object)?;
+        let new_value = catch(|| self.value.remove(&object))?;
 
-        Ok(new_self)
+        Ok(self.derive(new_value, old_value.map(|old_value| (&object, old_value))))
     }
 
     pub fn remove(&self, py_key: PyObject) -> PyResult<Self> {
-        let key = Object::new(py_key);
+        let key = Object::hashable(py_key)?;
 
-        if !self.value.contains_key(&key) {
-            return Err(PyErr::new::<exceptions::KeyError, _>(key.to_string()));
+        if !catch(|| self.value.contains_key(&key))? {
+            return Err(PyErr::new::<exceptions::KeyError, _>(extract_py_object(
+                Some(&key),
+            )?));
         }
 
-        let new_self = Self {
-            value: self.value.remove(&key),
-        };
+        let old_value = self.cached_value(&key)?;
+        let new_value = catch(|| self.value.remove(&key))?;
 
-        Ok(new_self)
+        Ok(self.derive(new_value, old_value.map(|old_value| (&key, old_value))))
     }
 
     pub fn get(&self, py_key: PyObject) -> PyResult<PyObject> {
-        let key = Object::new(py_key);
-        if !self.value.contains_key(&key) {
-            return Err(PyErr::new::<exceptions::KeyError, _>(key.to_string()));
+        let key = Object::hashable(py_key)?;
+        match catch(|| self.value.get(&key))? {
+            Some(value) => extract_py_object(Some(value)),
+            None => Err(PyErr::new::<exceptions::KeyError, _>(extract_py_object(
+                Some(&key),
+            )?)),
         }
-        extract_py_object(self.value.get(&key))
     }
 
-    pub fn keys(&self) -> PyResult<crate::vector::Vector> {
-        let mut keys = crate::vector::Vector::new();
-        for element in self.value.keys() {
-            keys = keys.append(extract_py_object(Some(element))?)?;
-        }
-        Ok(keys)
+    pub fn keys(&self) -> PyResult<KeysView> {
+        Ok(KeysView::new(self.value.clone()))
     }
 
-    pub fn values(&self) -> PyResult<crate::vector::Vector> {
-        let mut values = crate::vector::Vector::new();
-        for element in self.value.values() {
-            values = values.append(extract_py_object(Some(element))?)?;
-        }
-        Ok(values)
+    pub fn values(&self) -> PyResult<ValuesView> {
+        Ok(ValuesView::new(self.value.clone()))
     }
 
-    pub fn itervalues(&self) -> PyResult<crate::vector::Vector> {
-        self.values()
+    pub fn items(&self) -> PyResult<ItemsView> {
+        Ok(ItemsView::new(self.value.clone()))
     }
 
-    pub fn items(&self) -> PyResult<crate::vector::Vector> {
-        let gil_guard = Python::acquire_gil();
-        let py = gil_guard.python();
+    pub fn iterkeys(&self) -> PyResult<crate::iterators::PyObjectIterator> {
+        Ok(self.keys()?.iterator())
+    }
 
-        let mut items = crate::vector::Vector::new();
-        for (key, value) in self.value.iter() {
-            let element = vec![
-                extract_py_object(Some(key))?,
-                extract_py_object(Some(value))?,
-            ];
-            let element = PyTuple::new(py, element);
-            let element = Py::from(element);
-            items = items.append(element.into_py(py))?;
-        }
-        Ok(items)
+    pub fn itervalues(&self) -> PyResult<crate::iterators::PyObjectIterator> {
+        Ok(self.values()?.iterator())
     }
 
-    pub fn iteritems(&self) -> PyResult<crate::vector::Vector> {
-        self.items()
+    pub fn iteritems(&self) -> PyResult<crate::iterators::PyObjectPairIterator> {
+        Ok(self.items()?.iterator())
     }
 
-    #[allow(clippy::needless_pass_by_value)]
     #[args(args = "*")]
-    pub fn update(&self, args: &PyTuple) -> PyResult<Self> {
-        let gil_guard = Python::acquire_gil();
-        let py = gil_guard.python();
+    pub fn update(&self, py: Python, args: &PyTuple) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        let mut hash_state = self.hash_state.get();
+        for arg in args.iter() {
+            for_each_item(py, arg, |py_key, py_element| {
+                let key = Object::hashable(py_key)?;
+                let element = Object::new(py_element);
+
+                // Like `set`, the replaced item is toggled out of the cached hash.
+                if let Some(state) = hash_state {
+                    let old_element = catch(|| value.get(&key))?;
+                    let removed = old_element.map(|old_element| (&key, old_element));
+                    hash_state =
+                        toggle_items(state, removed.into_iter().chain(Some((&key, &element))));
+                }
+                catch(|| value.insert_mut(key, element))
+            })?;
+        }
 
-        let mut new_self = Self {
-            value: self.value.clone(),
-        };
+        Ok(Map {
+            value,
+            hash_state: Cell::new(hash_state),
+        })
+    }
 
-        for arg in args.iter() {
-            let object: PyObject = arg.into_py(py);
-            let iterator = arg.iter().unwrap();
+    #[must_use]
+    pub fn copy(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
 
-            for key in iterator {
-                let key: PyObject = key.unwrap().into_py(py);
-                let value = object.call_method1(py, "__getitem__", (key.clone_ref(py),))?;
-                new_self = new_self.set(key, value)?;
-            }
-        }
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
 
-        Ok(new_self)
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
     }
-}
 
-impl Hash for Map {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        // Add the hash of length so that if two collections are added one after the other it doesn't
-        // hash to the same thing as a single collection with the same elements in the same order.
-        self.value.size().hash(state);
-        for (key, value) in self.value.iter() {
-            key.hash(state);
-            value.hash(state);
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut value = slf.value.clone();
+        let mut copied = false;
+        for (key, element) in slf.value.iter() {
+            let key_copy = deepcopy_object(py, key, &memo)?;
+            let element_copy = deepcopy_object(py, element, &memo)?;
+            if key_copy.is_none() && element_copy.is_none() {
+                continue;
+            }
+
+            let element = element_copy.unwrap_or_else(|| element.clone());
+            catch(|| match key_copy {
+                Some(key_copy) => {
+                    value.remove_mut(key);
+                    value.insert_mut(key_copy, element);
+                }
+                None => value.insert_mut(key.clone(), element),
+            })?;
+            copied = true;
         }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+        Py::new(py, Self::from(value))
     }
 }
 
@@ -155,7 +263,8 @@ impl PySequenceProtocol for Map {
     }
 
     fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
-        Ok(self.value.contains_key(&Object::new(py_object)))
+        let object = Object::hashable(py_object)?;
+        catch(|| self.value.contains_key(&object))
     }
 }
 
@@ -169,14 +278,7 @@ impl PyMappingProtocol for Map {
 #[pyproto]
 impl PyIterProtocol for Map {
     fn __iter__(slf: PyRefMut<Self>) -> PyResult<crate::iterators::PyObjectIterator> {
-        let mut elements = std::vec::Vec::new();
-        for element in slf.value.keys() {
-            elements.push(extract_py_object(Some(element))?)
-        }
-
-        Ok(crate::iterators::PyObjectIterator::new(
-            elements.into_iter(),
-        ))
+        slf.iterkeys()
     }
 }
 
@@ -197,49 +299,77 @@ impl std::fmt::Display for Map {
     }
 }
 
-#[pyfunction(args = "*")]
-fn pmap(args: &PyTuple) -> PyResult<Map> {
-    let mut map = Map::new();
+/// Inserts an item in place, copying the path to it only while that is still shared.
+fn insert_item(map: &mut RpdsMap, key: PyObject, value: PyObject) -> PyResult<()> {
+    let key = Object::hashable(key)?;
+    catch(|| map.insert_mut(key, Object::new(value)))
+}
+
+/// Calls `insert` with every item of a mapping.
+pub fn for_each_item<F>(py: Python, items: &PyAny, mut insert: F) -> PyResult<()>
+where
+    F: FnMut(PyObject, PyObject) -> PyResult<()>,
+{
+    if let Ok(dict) = items.downcast::<PyDict>() {
+        for (key, element) in dict.iter() {
+            insert(key.to_object(py), element.to_object(py))?;
+        }
+    } else {
+        for key in items.iter()? {
+            let key = key?;
+            let element = items.get_item(key)?;
+            insert(key.to_object(py), element.to_object(py))?;
+        }
+    }
+    Ok(())
+}
+
+/// Validates the number of children of the HAMT nodes, which rpds requires to be a power of two
+/// no larger than the number of bits in a hash.
+pub fn checked_degree(degree: usize) -> PyResult<u8> {
+    let max_degree = 8 * std::mem::size_of::<usize>();
+    if !degree.is_power_of_two() || !(2..=max_degree).contains(&degree) {
+        return Err(PyErr::new::<exceptions::ValueError, _>(format!(
+            "degree must be a power of two between 2 and {}, got {}",
+            max_degree, degree
+        )));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(degree as u8)
+}
+
+#[pyfunction(args = "*", kwargs = "**")]
+fn pmap(py: Python, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<Map> {
+    let mut value = match keyword_only_argument("pmap", "degree", kwargs)? {
+        Some(degree) => RpdsMap::new_with_degree(checked_degree(degree)?),
+        None => RpdsMap::new(),
+    };
     if args.is_empty() {
-        return Ok(map);
+        return Ok(Map::from(value));
     } else if args.len() > 1 {
         return Err(PyErr::new::<exceptions::ValueError, _>(
             "Incorrect number of arguments!!",
         ));
     }
 
-    let gil_guard = Python::acquire_gil();
-    let py = gil_guard.python();
-
     let dict = args.get_item(0).extract::<Py<PyDict>>()?;
-    let dict = dict.as_ref(py);
-    for key_value_pair in dict.items() {
-        let key_value_pair = key_value_pair.downcast::<PyTuple>()?;
-        let key = key_value_pair.get_item(0).to_object(py);
-        let value = key_value_pair.get_item(1).to_object(py);
-        map = map.set(key, value)?;
+    for (key, element) in dict.as_ref(py).iter() {
+        insert_item(&mut value, key.to_object(py), element.to_object(py))?;
     }
-    Ok(map)
+    Ok(Map::from(value))
 }
 
 #[pyfunction(kwargs = "**")]
-fn m(kwargs: Option<&PyDict>) -> PyResult<Map> {
-    let mut map = Map::new();
+fn m(py: Python, kwargs: Option<&PyDict>) -> PyResult<Map> {
+    let mut value = RpdsMap::new();
 
-    if kwargs == None {
-        return Ok(map);
-    }
-    let kwargs = kwargs.unwrap();
-
-    let gil_guard = Python::acquire_gil();
-    let py = gil_guard.python();
-
-    for (key, value) in kwargs.iter() {
-        let key = key.to_object(py);
-        let value = value.to_object(py);
-        map = map.set(key, value)?;
+    if let Some(kwargs) = kwargs {
+        for (key, element) in kwargs.iter() {
+            insert_item(&mut value, key.to_object(py), element.to_object(py))?;
+        }
     }
-    Ok(map)
+    Ok(Map::from(value))
 }
 
 pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -0,0 +1,333 @@
+use pyo3::class::{PyNumberProtocol, PyObjectProtocol, PySequenceProtocol};
+use pyo3::prelude::{pyclass, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::types::{PyFrozenSet, PySet, PyTuple};
+use pyo3::{
+    exceptions, ObjectProtocol, PyAny, PyCell, PyErr, PyIterProtocol, PyNativeType, PyRefMut,
+    Python, ToPyObject,
+};
+
+use crate::iterators::{OwningIterator, PyObjectIterator, PyObjectPairIterator};
+use crate::object::{catch, extract_py_object, repr, Object};
+
+type RpdsMap = rpds::HashTrieMap<Object, Object>;
+
+#[pyclass(name = PMapKeysView)]
+pub struct KeysView {
+    value: RpdsMap,
+}
+
+#[pyclass(name = PMapValuesView)]
+pub struct ValuesView {
+    value: RpdsMap,
+}
+
+#[pyclass(name = PMapItemsView)]
+pub struct ItemsView {
+    value: RpdsMap,
+}
+
+impl KeysView {
+    #[must_use]
+    pub fn new(value: RpdsMap) -> Self {
+        KeysView { value }
+    }
+
+    #[must_use]
+    pub fn iterator(&self) -> PyObjectIterator {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.keys().map(|key| extract_py_object(Some(key)))
+            })
+        })
+    }
+
+    fn to_set<'p>(&self, py: Python<'p>) -> PyResult<&'p PySet> {
+        let set = PySet::empty(py)?;
+        for key in self.value.keys() {
+            set.add(extract_py_object(Some(key))?)?;
+        }
+        Ok(set)
+    }
+}
+
+impl ValuesView {
+    #[must_use]
+    pub fn new(value: RpdsMap) -> Self {
+        ValuesView { value }
+    }
+
+    #[must_use]
+    pub fn iterator(&self) -> PyObjectIterator {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.values().map(|value| extract_py_object(Some(value)))
+            })
+        })
+    }
+}
+
+impl ItemsView {
+    #[must_use]
+    pub fn new(value: RpdsMap) -> Self {
+        ItemsView { value }
+    }
+
+    #[must_use]
+    pub fn iterator(&self) -> PyObjectPairIterator {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        PyObjectPairIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.iter().map(|(key, value)| {
+                    Ok((
+                        extract_py_object(Some(key))?,
+                        extract_py_object(Some(value))?,
+                    ))
+                })
+            })
+        })
+    }
+
+    fn to_set<'p>(&self, py: Python<'p>) -> PyResult<&'p PySet> {
+        let set = PySet::empty(py)?;
+        for (key, value) in self.value.iter() {
+            set.add((
+                extract_py_object(Some(key))?,
+                extract_py_object(Some(value))?,
+            ))?;
+        }
+        Ok(set)
+    }
+}
+
+fn is_set_like(object: &PyAny) -> bool {
+    object.downcast::<PySet>().is_ok()
+        || object.downcast::<PyFrozenSet>().is_ok()
+        || object.downcast::<PyCell<KeysView>>().is_ok()
+        || object.downcast::<PyCell<ItemsView>>().is_ok()
+}
+
+fn to_set(object: &PyAny) -> PyResult<&PyAny> {
+    object.py().get_type::<PySet>().call1((object,))
+}
+
+/// Applies a binary `set` operation, as `dict` views do, accepting any iterable on either side.
+fn set_operation(lhs: &PyAny, rhs: &PyAny, operation: &str) -> PyResult<PyObject> {
+    let result = to_set(lhs)?.call_method1(operation, (to_set(rhs)?,))?;
+    Ok(result.to_object(lhs.py()))
+}
+
+fn set_comparison(set: &PySet, other: &PyAny, op: pyo3::class::basic::CompareOp) -> PyResult<bool> {
+    if !is_set_like(other) {
+        return match op {
+            pyo3::class::basic::CompareOp::Eq => Ok(false),
+            pyo3::class::basic::CompareOp::Ne => Ok(true),
+            _ => Err(PyErr::new::<exceptions::TypeError, _>(
+                "Invalid comparison operator!".to_string(),
+            )),
+        };
+    }
+    set.rich_compare(to_set(other)?, op)?
+        .extract::<bool>(other.py())
+}
+
+fn fmt_elements<'a, I: Iterator<Item = &'a Object>>(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    elements: I,
+) -> std::fmt::Result {
+    write!(f, "{}([", name)?;
+    for (index, element) in elements.enumerate() {
+        if index != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", element)?;
+    }
+    write!(f, "])")
+}
+
+#[pymethods]
+impl KeysView {
+    pub fn isdisjoint(&self, other: &PyAny) -> PyResult<bool> {
+        self.to_set(other.py())?
+            .call_method1("isdisjoint", (other,))?
+            .extract::<bool>()
+    }
+}
+
+#[pymethods]
+impl ItemsView {
+    pub fn isdisjoint(&self, other: &PyAny) -> PyResult<bool> {
+        self.to_set(other.py())?
+            .call_method1("isdisjoint", (other,))?
+            .extract::<bool>()
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for KeysView {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.size())
+    }
+
+    fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
+        let object = Object::hashable(py_object)?;
+        catch(|| self.value.contains_key(&object))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for ValuesView {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.size())
+    }
+
+    fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
+        let object = Object::new(py_object);
+        catch(|| self.value.values().any(|value| *value == object))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for ItemsView {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.size())
+    }
+
+    fn __contains__(&self, item: &PyAny) -> PyResult<bool> {
+        let item = match item.downcast::<PyTuple>() {
+            Ok(item) if item.len() == 2 => item,
+            _ => return Ok(false),
+        };
+
+        let py = item.py();
+        let key = Object::hashable(item.get_item(0).to_object(py))?;
+        let value = Object::new(item.get_item(1).to_object(py));
+        catch(|| match self.value.get(&key) {
+            Some(element) => *element == value,
+            None => false,
+        })
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for KeysView {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectIterator> {
+        Ok(slf.iterator())
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for ValuesView {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectIterator> {
+        Ok(slf.iterator())
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for ItemsView {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectPairIterator> {
+        Ok(slf.iterator())
+    }
+}
+
+#[pyproto]
+impl PyNumberProtocol for KeysView {
+    fn __and__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__and__")
+    }
+
+    fn __or__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__or__")
+    }
+
+    fn __sub__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__sub__")
+    }
+
+    fn __xor__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__xor__")
+    }
+}
+
+#[pyproto]
+impl PyNumberProtocol for ItemsView {
+    fn __and__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__and__")
+    }
+
+    fn __or__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__or__")
+    }
+
+    fn __sub__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__sub__")
+    }
+
+    fn __xor__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        set_operation(lhs, rhs, "__xor__")
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for KeysView {
+    fn __richcmp__(&self, other: &PyAny, op: pyo3::class::basic::CompareOp) -> PyResult<bool> {
+        set_comparison(self.to_set(other.py())?, other, op)
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        repr(self)
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for ValuesView {
+    fn __repr__(&self) -> PyResult<String> {
+        repr(self)
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for ItemsView {
+    fn __richcmp__(&self, other: &PyAny, op: pyo3::class::basic::CompareOp) -> PyResult<bool> {
+        set_comparison(self.to_set(other.py())?, other, op)
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        repr(self)
+    }
+}
+
+impl std::fmt::Display for KeysView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_elements(f, "PMapKeysView", self.value.keys())
+    }
+}
+
+impl std::fmt::Display for ValuesView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_elements(f, "PMapValuesView", self.value.values())
+    }
+}
+
+impl std::fmt::Display for ItemsView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PMapItemsView([")?;
+        for (index, (key, value)) in self.value.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "({}, {})", key, value)?;
+        }
+        write!(f, "])")
+    }
+}
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<KeysView>()?;
+    m.add_class::<ValuesView>()?;
+    m.add_class::<ItemsView>()?;
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::mem;
+
+use pyo3::prelude::{pyfunction, PyModule, PyResult};
+use pyo3::types::PyTuple;
+use pyo3::{exceptions, wrap_pyfunction, AsPyPointer, ObjectProtocol, PyAny, PyErr, PyRef, Python};
+
+use crate::primitive_vector::{BoolVector, F64Vector, I64Vector};
+use crate::rrb_vector::RrbVector;
+use crate::sorted_map::SortedMap;
+use crate::sorted_set::SortedSet;
+use crate::{List, Map, Set, Vector};
+
+/// The bytes of a reference counted allocation holding a `T`, i.e. the strong and weak counts
+/// followed by the value.
+#[must_use]
+pub const fn cell_bytes<T>() -> usize {
+    2 * mem::size_of::<usize>() + mem::size_of::<T>()
+}
+
+// The sizes below are estimates of the rpds nodes, which are private: they are charged per element
+// and don't follow the actual layout of a container.
+
+/// The leaf node of a hash trie holding an entry, along with the pointer to it in its branch.
+pub const ESTIMATED_HASH_TRIE_NODE_BYTES: usize = 8 * mem::size_of::<usize>();
+
+/// The node of a list, pointing to an element and to the next node.
+pub const ESTIMATED_LIST_NODE_BYTES: usize = cell_bytes::<[usize; 2]>();
+
+/// The pointer to an element in a leaf of a vector. The branches are a small fraction of the leaves.
+pub const ESTIMATED_VECTOR_SLOT_BYTES: usize = mem::size_of::<usize>();
+
+/// The node of a red-black tree, pointing to an entry and to its children, along with its color.
+pub const ESTIMATED_RED_BLACK_TREE_NODE_BYTES: usize = cell_bytes::<[usize; 4]>();
+
+/// Accumulates the memory held by persistent containers, counting what they share only once.
+///
+/// rpds keeps its nodes private, but it stores every element (every entry of a hash trie)
+/// in an allocation of its own, which the versions of a container share until the element is
+/// replaced. These cells are told apart by their address. The nodes pointing to the cells
+/// can't be observed, they are estimated per cell, as if they were shared in the same proportion.
+/// The result is therefore an estimate: two versions sharing their elements but not their nodes,
+/// e.g. after the same elements were inserted again, are counted as sharing the nodes too.
+/// The nodes of the RRB vectors are ours, they are walked and counted exactly instead.
+#[derive(Default)]
+pub struct MemoryUsage {
+    containers: HashSet<usize>,
+    cells: HashSet<usize>,
+    nodes: HashSet<usize>,
+    bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Adds the memory of the Python object wrapping a container, unless it was already added.
+    pub fn add_container<T>(&mut self, object: &PyRef<T>) -> bool
+    where
+        T: pyo3::PyClass,
+    {
+        if !self.containers.insert(object.as_ptr() as usize) {
+            return false;
+        }
+        self.bytes += mem::size_of::<pyo3::PyCell<T>>();
+        true
+    }
+
+    /// Adds the cells of the elements which weren't added yet. Every cell takes `cell_bytes`,
+    /// the nodes pointing to it are estimated to take `node_bytes`.
+    pub fn add_cells<'a, T: 'a, I: IntoIterator<Item = &'a T>>(
+        &mut self,
+        elements: I,
+        cell_bytes: usize,
+        node_bytes: usize,
+    ) {
+        for element in elements {
+            let cell: *const T = element;
+            if self.cells.insert(cell as usize) {
+                self.bytes += cell_bytes + node_bytes;
+            }
+        }
+    }
+
+    /// Adds a node taking `bytes`, unless it was already added. Returns whether it was added,
+    /// if not, the nodes below it were added along with it.
+    pub fn add_node(&mut self, address: usize, bytes: usize) -> bool {
+        if !self.nodes.insert(address) {
+            return false;
+        }
+        self.bytes += bytes;
+        true
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+/// Implemented by the containers to add their elements to a `MemoryUsage`.
+pub trait Footprint {
+    fn add_cells(&self, usage: &mut MemoryUsage);
+
+    /// The bytes of a single container, as reported by `__sizeof__`.
+    #[must_use]
+    fn size_of(slf: &PyRef<Self>) -> usize
+    where
+        Self: pyo3::PyClass + Sized,
+    {
+        let mut usage = MemoryUsage::default();
+        usage.add_container(slf);
+        slf.add_cells(&mut usage);
+        usage.bytes()
+    }
+}
+
+fn add_footprint<T: pyo3::PyClass + Footprint>(usage: &mut MemoryUsage, container: &PyRef<T>) {
+    if usage.add_container(container) {
+        container.add_cells(usage);
+    }
+}
+
+fn add_any(usage: &mut MemoryUsage, object: &PyAny) -> PyResult<()> {
+    if let Ok(container) = object.extract::<PyRef<Vector>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<List>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<Map>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<Set>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<SortedMap>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<SortedSet>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<RrbVector>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<I64Vector>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<F64Vector>>() {
+        add_footprint(usage, &container);
+    } else if let Ok(container) = object.extract::<PyRef<BoolVector>>() {
+        add_footprint(usage, &container);
+    } else {
+        return Err(PyErr::new::<exceptions::TypeError, _>(format!(
+            "Expected a persistent container, got {}!",
+            object.get_type().name()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the bytes held by the containers together, counting the memory they share once.
+pub fn total_bytes<'p, I: IntoIterator<Item = &'p PyAny>>(containers: I) -> PyResult<usize> {
+    let mut usage = MemoryUsage::default();
+    for container in containers {
+        add_any(&mut usage, container)?;
+    }
+    Ok(usage.bytes())
+}
+
+/// Returns the bytes `extra` holds on top of the memory it shares with `base`.
+pub fn extra_bytes(base: &PyAny, extra: &PyAny) -> PyResult<usize> {
+    let mut usage = MemoryUsage::default();
+    add_any(&mut usage, base)?;
+    let base_bytes = usage.bytes();
+    add_any(&mut usage, extra)?;
+    Ok(usage.bytes() - base_bytes)
+}
+
+/// Returns the bytes held by all the given containers together. The memory they share,
+/// e.g. because some of them were derived from the others, is only counted once.
+///
+/// This is an estimate. The elements are stored in cells of their own, which are counted exactly
+/// and told apart by address, but the internal nodes aren't observable: each element is charged
+/// a fixed estimate of the node bytes pointing to it, and a node counts as shared whenever the
+/// elements it holds are. Only the nodes of `pvector_rrb` are walked and counted exactly.
+#[pyfunction(containers = "*")]
+fn memory_usage(containers: &PyTuple) -> PyResult<usize> {
+    total_bytes(containers.iter())
+}
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_wrapped(wrap_pyfunction!(memory_usage)).unwrap();
+
+    Ok(())
+}
@@ -1,96 +1,326 @@
-use pyo3::{exceptions, import_exception, PyErr, PyObject, PyResult, Python};
+use pyo3::types::{PyDict, PyString};
+use pyo3::{
+    exceptions, ffi, import_exception, AsPyPointer, AsPyRef, FromPyObject, ObjectProtocol, PyErr,
+    PyObject, PyResult, Python, ToPyObject,
+};
+use std::cell::{Cell, RefCell};
 use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
+use std::os::raw::c_int;
 
 import_exception!(io, UnsupportedOperation);
 
-pub struct Object(PyObject);
+thread_local! {
+    // `PartialEq` and `Hash` can't fail, so the first exception raised by `__eq__` or `__hash__`
+    // is parked here until the rpds operation returns, see `catch`.
+    static PENDING_ERROR: RefCell<Option<PyErr>> = RefCell::default();
+}
+
+fn has_pending_error() -> bool {
+    PENDING_ERROR.with(|pending| pending.borrow().is_some())
+}
+
+fn defer_error(error: PyErr) {
+    PENDING_ERROR.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.is_none() {
+            *pending = Some(error);
+        }
+    });
+}
+
+/// Runs an operation that compares or hashes `Object`s and raises the first Python exception
+/// encountered along the way. The result of a failed operation is discarded,
+/// therefore the persistent containers involved are left unchanged.
+pub fn catch<T, F: FnOnce() -> T>(operation: F) -> PyResult<T> {
+    // Operations can nest, e.g. when a container is an element of another container.
+    let outer_error = PENDING_ERROR.with(|pending| pending.borrow_mut().take());
+    let result = operation();
+    let error = PENDING_ERROR.with(|pending| pending.replace(outer_error));
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
+/// Parks the exception of a failed operation until `catch` returns, for the code which can't
+/// fail, like `PartialEq`. Returns `None` if the operation failed.
+pub fn park<T>(result: PyResult<T>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            defer_error(error);
+            None
+        }
+    }
+}
+
+/// Returns the token of the GIL held by the calling thread, for the code that isn't handed one:
+/// the `PartialEq`, `Hash`, `Clone` and `Display` implementations called by rpds and the
+/// protocol methods that pyo3 doesn't pass a `Python` to. Acquiring the GIL again for every
+/// single operation that rpds performs is pure overhead.
+///
+/// # Safety
+///
+/// The calling thread must hold the GIL. It does while a method of a container runs, since
+/// Python calls it on behalf of the caller, unless the method released it through
+/// `Python::allow_threads`.
+#[must_use]
+pub(crate) unsafe fn gil_held<'p>() -> Python<'p> {
+    Python::assume_gil_acquired()
+}
+
+/// A Python object held by a container. Objects are only touched by container methods, which
+/// hold the GIL, hence the `gil_held` calls in the trait implementations rpds relies on.
+/// For the same reason, an `Object` isn't `Sync`, so that code running without the GIL, see
+/// `Python::allow_threads`, can't reach it through a shared container.
+pub struct Object {
+    // Released by hand, see `Drop`.
+    object: ManuallyDrop<PyObject>,
+    // The result of `__hash__`, computed on first use.
+    hash: Cell<Option<isize>>,
+}
 
 impl Object {
     #[must_use]
     pub fn new(py_object: PyObject) -> Self {
-        Object { 0: py_object }
+        Object {
+            object: ManuallyDrop::new(py_object),
+            hash: Cell::new(None),
+        }
+    }
+
+    /// Wraps a key of a hash trie. Its hash is computed right away, so that an unhashable key
+    /// raises a `TypeError` before the trie is walked.
+    pub fn hashable(py_object: PyObject) -> PyResult<Self> {
+        let object = Self::new(py_object);
+        object.py_hash()?;
+        Ok(object)
+    }
+
+    /// The hash of the wrapped Python object, as returned by `hash()`.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
+        }
+
+        let py = unsafe { gil_held() };
+
+        let hash = hash_object(py, self)?;
+        self.hash.set(Some(hash));
+        Ok(hash)
     }
 }
 
 impl PartialEq for Object {
+    /// Identity-first equality, the way Python containers compare their elements.
     #[inline]
     fn eq(&self, object: &Object) -> bool {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-
-        let args = (&object.0,);
-        let py_eq = self.0.call_method1(py, "__eq__", args);
-        let eq = match py_eq {
-            Err(_) => Err(PyErr::new::<exceptions::NotImplementedError, _>(
-                "__eq__ method is not implemented!",
-            )),
-            Ok(x) => x.extract::<bool>(py),
+        if self.object.as_ptr() == object.object.as_ptr() {
+            return true;
+        }
+        // Once an exception has been raised, no more Python code should run.
+        if has_pending_error() {
+            return false;
+        }
+
+        let py = unsafe { gil_held() };
+
+        // Falls back on the reflected `__eq__` and on identity, as `==` does.
+        let eq = unsafe {
+            ffi::PyObject_RichCompareBool(self.object.as_ptr(), object.object.as_ptr(), ffi::Py_EQ)
         };
-        match eq {
-            Ok(eq) => eq,
-            _ => panic!("__eq__ failed!"),
+        if eq == -1 {
+            defer_error(PyErr::fetch(py));
+            return false;
         }
+        eq == 1
     }
 }
 
 impl Eq for Object {}
 
+impl Object {
+    /// Calls the wrapped object, e.g. a key function, with `argument`.
+    pub fn call1(&self, py: Python, argument: &Object) -> PyResult<Object> {
+        let result = self.object.call1(py, (argument.object.clone_ref(py),))?;
+        Ok(Object::new(result))
+    }
+
+    /// Compares with Python's `<`. Like `eq`, an exception is parked until `catch` returns.
+    #[must_use]
+    pub fn less_than(&self, object: &Object) -> bool {
+        if has_pending_error() {
+            return false;
+        }
+
+        let py = unsafe { gil_held() };
+
+        let lt = unsafe {
+            ffi::PyObject_RichCompareBool(self.object.as_ptr(), object.object.as_ptr(), ffi::Py_LT)
+        };
+        if lt == -1 {
+            defer_error(PyErr::fetch(py));
+            return false;
+        }
+        lt == 1
+    }
+}
+
 fn hash_object(py: Python, object: &Object) -> PyResult<isize> {
-    let element_hash_object = object.0.call_method0(py, "__hash__");
-    match element_hash_object {
-        Err(_) => Err(PyErr::new::<exceptions::NotImplementedError, _>(
-            "__hash__ method is not implemented!",
-        )),
-        Ok(x) => x.extract::<isize>(py),
+    // Unlike calling `__hash__`, this rejects unhashable objects and folds the result into a `Py_hash_t`.
+    let hash = unsafe { ffi::PyObject_Hash(object.object.as_ptr()) };
+    if hash == -1 {
+        return Err(PyErr::fetch(py));
     }
+    Ok(hash)
 }
 
 impl Hash for Object {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-        let object_hash_result = hash_object(py, self);
+        if has_pending_error() {
+            return;
+        }
 
-        match object_hash_result {
+        match self.py_hash() {
             Ok(object_hash) => object_hash.hash(state),
-            _ => panic!("__hash__ failed!"),
+            Err(error) => defer_error(error),
         };
     }
 }
 
 impl Clone for Object {
     fn clone(&self) -> Self {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
+        let py = unsafe { gil_held() };
 
         Self {
-            0: self.0.clone_ref(py),
+            object: ManuallyDrop::new(self.object.clone_ref(py)),
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+extern "C" {
+    // Not exposed by `pyo3::ffi`.
+    fn PyGILState_Check() -> c_int;
+}
+
+impl Drop for Object {
+    fn drop(&mut self) {
+        // Dropping a `PyObject` defers the decref to pyo3's release pool. When the element is
+        // itself a container, freeing it drains the pool again while the pool is being drained,
+        // which the pool doesn't support. Whenever the GIL is held, the reference is released
+        // right away instead, the way CPython containers release their elements.
+        unsafe {
+            if PyGILState_Check() == 1 {
+                ffi::Py_DECREF(self.object.as_ptr());
+            } else {
+                ManuallyDrop::drop(&mut self.object);
+            }
         }
     }
 }
 
 impl std::fmt::Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-        let repr = self.0.call_method0(py, "__repr__");
+        let py = unsafe { gil_held() };
+
+        let repr = self.object.as_ref(py).repr().and_then(PyString::to_string);
         match repr {
-            Ok(x) => write!(f, "{}", x.extract::<String>(py).unwrap()),
-            Err(_) => Err(std::fmt::Error::default()),
+            Ok(x) => write!(f, "{}", x),
+            Err(error) => {
+                defer_error(error);
+                Err(std::fmt::Error::default())
+            }
         }
     }
 }
 
+thread_local! {
+    // Addresses of the containers whose repr is being computed on this thread.
+    static REPR_IN_PROGRESS: RefCell<Vec<usize>> = RefCell::default();
+}
+
+/// Computes the repr of a container through its `Display` implementation.
+/// Raises the exception of any element whose `__repr__` fails and prints `...`
+/// for a container which (through a mutable element) contains itself.
+pub fn repr<T: std::fmt::Display>(container: &T) -> PyResult<String> {
+    let address: *const T = container;
+    let address = address as usize;
+    let recursive = REPR_IN_PROGRESS.with(|in_progress| {
+        let mut in_progress = in_progress.borrow_mut();
+        // Cut as soon as the container is reached again, whether or not the objects in between
+        // guard their own repr.
+        if in_progress.contains(&address) {
+            return true;
+        }
+        in_progress.push(address);
+        false
+    });
+    if recursive {
+        return Ok("...".to_string());
+    }
+
+    let mut repr = String::new();
+    let result = catch(|| std::fmt::write(&mut repr, format_args!("{}", container)));
+    REPR_IN_PROGRESS.with(|in_progress| in_progress.borrow_mut().pop());
+
+    match result? {
+        Ok(()) => Ok(repr),
+        Err(_) => Err(PyErr::new::<exceptions::RuntimeError, _>(
+            "Failed to compute the repr!",
+        )),
+    }
+}
+
+/// Deep-copies the wrapped Python object through `copy.deepcopy`.
+/// Returns `None` when the copy is the very same object (i.e. the element is immutable),
+/// so that containers can keep sharing their structure.
+pub fn deepcopy_object(py: Python, object: &Object, memo: &PyObject) -> PyResult<Option<Object>> {
+    let copy = py.import("copy")?.call1(
+        "deepcopy",
+        (object.object.clone_ref(py), memo.clone_ref(py)),
+    )?;
+
+    if copy.as_ptr() == object.object.as_ptr() {
+        return Ok(None);
+    }
+    Ok(Some(Object::new(copy.to_object(py))))
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub fn extract_py_object(object: Option<&Object>) -> PyResult<PyObject> {
-    let gil = Python::acquire_gil();
-    let py = gil.python();
+    let py = unsafe { gil_held() };
 
     match object {
-        Some(object) => Ok(object.0.clone_ref(py)),
+        Some(object) => Ok(object.object.clone_ref(py)),
         None => Err(PyErr::new::<exceptions::RuntimeError, _>(
             "Invalid call. Most likely container is empty!",
         )),
     }
 }
+
+/// Takes the single keyword-only argument of a function that also accepts `*args`. pyo3 can't
+/// declare it as a parameter, it would take the first positional argument for it.
+pub fn keyword_only_argument<'p, T: FromPyObject<'p>>(
+    function: &str,
+    name: &str,
+    kwargs: Option<&'p PyDict>,
+) -> PyResult<Option<T>> {
+    if let Some(kwargs) = kwargs {
+        for key in kwargs.keys() {
+            if key.extract::<&str>()? != name {
+                return Err(PyErr::new::<exceptions::TypeError, _>(format!(
+                    "{}() got an unexpected keyword argument: {}",
+                    function, key
+                )));
+            }
+        }
+        if let Some(value) = kwargs.get_item(name) {
+            return value.extract::<Option<T>>();
+        }
+    }
+    Ok(None)
+}
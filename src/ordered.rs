@@ -0,0 +1,320 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use pyo3::prelude::PyResult;
+use pyo3::Python;
+use rpds::RedBlackTreeMap;
+
+use crate::memory::{cell_bytes, MemoryUsage, ESTIMATED_RED_BLACK_TREE_NODE_BYTES};
+use crate::object::{gil_held, park, Object};
+
+/// The sort key of an entry of a sorted container, ordered with Python's `<`. Sort keys neither
+/// of which is less than the other are equivalent, as for `sorted`.
+///
+/// Comparisons can't fail, an exception raised by `<` is parked until `catch` returns.
+#[derive(Clone)]
+pub struct SortKey {
+    object: Object,
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.object.less_than(&other.object) {
+            Ordering::Less
+        } else if other.object.less_than(&self.object) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey {}
+
+// The entries whose sort keys are equivalent, in the order they were inserted.
+type Bucket<V> = Vec<(Object, V)>;
+
+/// The entries of a sorted container, i.e. its keys along with their values, ordered by the
+/// sort keys. Those are the keys themselves, or the results of a key function.
+///
+/// The red-black tree is ordered by the sort keys only, so the entries whose sort keys are
+/// equivalent share a node of the tree, where their keys are told apart with `==`.
+/// Two keys are therefore the same key only if they are equal, as in a `dict`.
+///
+/// The methods comparing keys may raise through their `__lt__` and `__eq__`, see `catch`.
+pub struct SortedEntries<V> {
+    tree: RedBlackTreeMap<SortKey, Bucket<V>>,
+    size: usize,
+    // The function computing the sort keys, `None` to sort the keys themselves.
+    key: Option<Object>,
+}
+
+impl<V: Clone> Clone for SortedEntries<V> {
+    fn clone(&self) -> Self {
+        SortedEntries {
+            tree: self.tree.clone(),
+            size: self.size,
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<V> Default for SortedEntries<V> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Borrows the value of a bound.
+fn bound_ref<T>(bound: &Bound<T>) -> Bound<&T> {
+    match bound {
+        Bound::Included(value) => Bound::Included(value),
+        Bound::Excluded(value) => Bound::Excluded(value),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl<V> SortedEntries<V> {
+    #[must_use]
+    pub fn new(key: Option<Object>) -> Self {
+        SortedEntries {
+            tree: RedBlackTreeMap::new(),
+            size: 0,
+            key,
+        }
+    }
+
+    /// No entries, with the same key function.
+    #[must_use]
+    pub fn empty_copy(&self) -> Self {
+        Self::new(self.key.clone())
+    }
+
+    /// The function computing the sort keys, if any.
+    #[must_use]
+    pub fn key_function(&self) -> Option<&Object> {
+        self.key.as_ref()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Computes the sort key of `key`, calling the key function if there is one.
+    pub fn sort_key(&self, py: Python, key: &Object) -> PyResult<SortKey> {
+        let object = match &self.key {
+            Some(function) => function.call1(py, key)?,
+            None => key.clone(),
+        };
+        Ok(SortKey { object })
+    }
+
+    /// The node of the tree holding the entries whose sort keys are equivalent to `sort_key`.
+    fn node(&self, sort_key: &SortKey) -> Option<(&SortKey, &Bucket<V>)> {
+        let bounds = (Bound::Included(sort_key), Bound::Included(sort_key));
+        self.tree.range(bounds).next()
+    }
+
+    /// Looks up the entry of `key`, whose sort key is `sort_key`.
+    #[must_use]
+    pub fn get(&self, sort_key: &SortKey, key: &Object) -> Option<(&Object, &V)> {
+        let (_, bucket) = self.node(sort_key)?;
+        bucket
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(key, value)| (key, value))
+    }
+
+    /// Iterates over the entries in order, the entries with equivalent sort keys in the order
+    /// they were inserted.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&Object, &V)> {
+        self.tree
+            .values()
+            .flat_map(|bucket| bucket.iter().map(|(key, value)| (key, value)))
+    }
+
+    /// Iterates over the entries in order along with their sort keys.
+    pub fn iter_with_sort_keys(&self) -> impl Iterator<Item = (&SortKey, &Object, &V)> {
+        self.tree.iter().flat_map(|(sort_key, bucket)| {
+            bucket
+                .iter()
+                .map(move |(key, value)| (sort_key, key, value))
+        })
+    }
+
+    #[must_use]
+    pub fn first(&self) -> Option<(&Object, &V)> {
+        let (_, bucket) = self.tree.first()?;
+        bucket.first().map(|(key, value)| (key, value))
+    }
+
+    #[must_use]
+    pub fn last(&self) -> Option<(&Object, &V)> {
+        let (_, bucket) = self.tree.last()?;
+        bucket.last().map(|(key, value)| (key, value))
+    }
+
+    /// Iterates in order over the entries whose sort keys lie between `low` and `high`.
+    ///
+    /// Only the low bound is handed to rpds, which panics on bounds in the wrong order.
+    /// The iteration stops at the first entry past the high bound instead, so bounds in
+    /// the wrong order make an empty range. Nothing is compared until the first step.
+    pub fn range<'a>(
+        &'a self,
+        low: &'a Bound<SortKey>,
+        high: &'a Bound<SortKey>,
+    ) -> impl Iterator<Item = (&'a Object, &'a V)> {
+        let mut nodes = None;
+        std::iter::from_fn(move || {
+            let nodes =
+                nodes.get_or_insert_with(|| self.tree.range((bound_ref(low), Bound::Unbounded)));
+            nodes.next()
+        })
+        .take_while(move |(sort_key, _)| match high {
+            Bound::Included(high) => *sort_key <= high,
+            Bound::Excluded(high) => *sort_key < high,
+            Bound::Unbounded => true,
+        })
+        .flat_map(|(_, bucket)| bucket.iter().map(|(key, value)| (key, value)))
+    }
+
+    /// Iterates backwards over the entries whose sort keys are before `high`.
+    pub fn range_back<'a>(
+        &'a self,
+        high: &'a Bound<SortKey>,
+    ) -> impl Iterator<Item = (&'a Object, &'a V)> {
+        self.tree
+            .range((Bound::Unbounded, bound_ref(high)))
+            .rev()
+            .flat_map(|(_, bucket)| bucket.iter().rev().map(|(key, value)| (key, value)))
+    }
+
+    /// Counts the entries whose sort keys are less than `sort_key`.
+    ///
+    /// rpds doesn't keep the sizes of the subtrees, so this walks the nodes before `sort_key`,
+    /// in time linear in the rank rather than logarithmic.
+    #[must_use]
+    pub fn rank(&self, sort_key: &SortKey) -> usize {
+        self.tree
+            .range((Bound::Unbounded, Bound::Excluded(sort_key)))
+            .map(|(_, bucket)| bucket.len())
+            .sum()
+    }
+
+    /// The position of the entry of `key`, whose sort key is `sort_key`, if there is one.
+    /// Takes time linear in the position, see `rank`.
+    #[must_use]
+    pub fn position(&self, sort_key: &SortKey, key: &Object) -> Option<usize> {
+        let (_, bucket) = self.node(sort_key)?;
+        let index = bucket.iter().position(|(other, _)| other == key)?;
+        Some(self.rank(sort_key) + index)
+    }
+
+    /// Adds the nodes of the tree to a `MemoryUsage`, see `Footprint`.
+    pub fn add_cells(&self, usage: &mut MemoryUsage) {
+        // A bucket holds a single entry unless keys have equivalent sort keys.
+        usage.add_cells(
+            self.tree.keys(),
+            cell_bytes::<(SortKey, Bucket<V>)>() + std::mem::size_of::<(Object, V)>(),
+            ESTIMATED_RED_BLACK_TREE_NODE_BYTES,
+        );
+    }
+}
+
+impl<V: Clone> SortedEntries<V> {
+    /// Adds the entry of `key`, whose sort key is `sort_key`, replacing the one of an equal key.
+    pub fn insert_mut(&mut self, sort_key: SortKey, key: Object, value: V) {
+        let (sort_key, mut bucket) = match self.node(&sort_key) {
+            Some((node_key, bucket)) => (node_key.clone(), bucket.clone()),
+            None => (sort_key, Vec::with_capacity(1)),
+        };
+        if let Some(index) = bucket.iter().position(|(other, _)| *other == key) {
+            bucket[index] = (key, value);
+        } else {
+            bucket.push((key, value));
+            self.size += 1;
+        }
+        self.tree.insert_mut(sort_key, bucket);
+    }
+
+    #[must_use]
+    pub fn insert(&self, sort_key: SortKey, key: Object, value: V) -> Self {
+        let mut entries = self.clone();
+        entries.insert_mut(sort_key, key, value);
+        entries
+    }
+
+    /// Removes the entry of `key`, whose sort key is `sort_key`. Returns `None` if there is none.
+    #[must_use]
+    pub fn remove(&self, sort_key: &SortKey, key: &Object) -> Option<Self> {
+        let (node_key, bucket) = self.node(sort_key)?;
+        let index = bucket.iter().position(|(other, _)| other == key)?;
+        Some(self.without(node_key, bucket, index))
+    }
+
+    /// Removes the first entry. Returns `None` if there is none.
+    #[must_use]
+    pub fn without_first(&self) -> Option<Self> {
+        let (node_key, bucket) = self.tree.first()?;
+        Some(self.without(node_key, bucket, 0))
+    }
+
+    fn without(&self, node_key: &SortKey, bucket: &Bucket<V>, index: usize) -> Self {
+        let tree = if bucket.len() == 1 {
+            self.tree.remove(node_key)
+        } else {
+            let mut bucket = bucket.clone();
+            bucket.remove(index);
+            self.tree.insert(node_key.clone(), bucket)
+        };
+        SortedEntries {
+            tree,
+            size: self.size - 1,
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<V: Clone + PartialEq> PartialEq for SortedEntries<V> {
+    /// The entries are equal if they have equal keys with equal values, whatever their order.
+    fn eq(&self, other: &Self) -> bool {
+        if self.size != other.size {
+            return false;
+        }
+        // Only called by the methods of the containers, which hold the GIL.
+        let py = unsafe { gil_held() };
+
+        let same_sort_keys = self.key == other.key;
+        self.tree.iter().all(|(sort_key, bucket)| {
+            bucket.iter().all(|(key, value)| {
+                let other_sort_key = if same_sort_keys {
+                    Some(sort_key.clone())
+                } else {
+                    park(other.sort_key(py, key))
+                };
+                let other_value = other_sort_key
+                    .and_then(|other_sort_key| other.get(&other_sort_key, key))
+                    .map(|(_, other_value)| other_value);
+                other_value == Some(value)
+            })
+        })
+    }
+}
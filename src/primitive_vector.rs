@@ -0,0 +1,563 @@
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use pyo3::buffer::{ElementType, PyBuffer};
+use pyo3::class::basic::CompareOp;
+use pyo3::class::{PyObjectProtocol, PySequenceProtocol};
+use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::types::{PyBytes, PyTuple};
+use pyo3::{
+    exceptions, wrap_pyfunction, FromPyObject, IntoPy, ObjectProtocol, Py, PyAny, PyCell, PyErr,
+    PyIterProtocol, PyNativeType, PyRef, PyRefMut, Python, ToPyObject,
+};
+
+use crate::iterators::{Indexed, PyObjectIterator, VectorCursor};
+use crate::memory::{cell_bytes, Footprint, MemoryUsage, ESTIMATED_VECTOR_SLOT_BYTES};
+use crate::object::{gil_held, Object};
+use crate::vector::{normalize_index, normalize_range, without_range};
+
+/// An element type stored unboxed. Elements are compared, hashed and summed natively
+/// and only converted to Python objects when they leave the vector, so the vectors are
+/// `Sync` and the loops over their elements run without holding the GIL.
+pub trait Primitive:
+    Copy + PartialEq + Send + Sync + IntoPy<PyObject> + for<'a> FromPyObject<'a> + 'static
+{
+    /// The type of the sum of the elements, wide enough for the sum not to overflow.
+    type Sum: IntoPy<PyObject> + Send;
+
+    /// The `struct` format character of the elements in a buffer.
+    const FORMAT: &'static str;
+
+    /// The type of the elements of the buffers which can be read without a conversion.
+    const ELEMENT_TYPE: ElementType;
+
+    fn hash_element<H: Hasher>(self, state: &mut H);
+
+    fn sum<I: Iterator<Item = Self>>(elements: I) -> Self::Sum;
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>);
+
+    fn from_ne_bytes(bytes: &[u8]) -> Self;
+}
+
+impl Primitive for i64 {
+    // The sum of fewer than 2 ** 64 elements fits.
+    type Sum = i128;
+
+    const FORMAT: &'static str = "q";
+    const ELEMENT_TYPE: ElementType = ElementType::SignedInteger { bytes: 8 };
+
+    fn hash_element<H: Hasher>(self, state: &mut H) {
+        self.hash(state);
+    }
+
+    fn sum<I: Iterator<Item = Self>>(elements: I) -> Self::Sum {
+        elements.map(i128::from).sum()
+    }
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn from_ne_bytes(bytes: &[u8]) -> Self {
+        let mut array = [0; 8];
+        array.copy_from_slice(bytes);
+        i64::from_ne_bytes(array)
+    }
+}
+
+impl Primitive for f64 {
+    type Sum = f64;
+
+    const FORMAT: &'static str = "d";
+    const ELEMENT_TYPE: ElementType = ElementType::Float { bytes: 8 };
+
+    fn hash_element<H: Hasher>(self, state: &mut H) {
+        // Adding 0.0 turns -0.0 into 0.0, the two compare equal so they must hash the same.
+        (self + 0.0).to_bits().hash(state);
+    }
+
+    fn sum<I: Iterator<Item = Self>>(elements: I) -> Self::Sum {
+        elements.sum()
+    }
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn from_ne_bytes(bytes: &[u8]) -> Self {
+        let mut array = [0; 8];
+        array.copy_from_slice(bytes);
+        f64::from_ne_bytes(array)
+    }
+}
+
+impl Primitive for bool {
+    type Sum = usize;
+
+    const FORMAT: &'static str = "?";
+    const ELEMENT_TYPE: ElementType = ElementType::Bool;
+
+    fn hash_element<H: Hasher>(self, state: &mut H) {
+        self.hash(state);
+    }
+
+    fn sum<I: Iterator<Item = Self>>(elements: I) -> Self::Sum {
+        elements.filter(|element| *element).count()
+    }
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.push(u8::from(self));
+    }
+
+    fn from_ne_bytes(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn hash_elements<T: Primitive>(vector: &rpds::VectorSync<T>) -> isize {
+    let mut hasher = DefaultHasher::new();
+    vector.len().hash(&mut hasher);
+    for element in vector.iter() {
+        element.hash_element(&mut hasher);
+    }
+    hasher.finish() as isize
+}
+
+/// The elements from `start` to `stop`.
+fn elements<T: Primitive>(
+    vector: &rpds::VectorSync<T>,
+    start: usize,
+    stop: usize,
+) -> impl Iterator<Item = &T> {
+    vector.iter().skip(start).take(stop.saturating_sub(start))
+}
+
+/// Compares an element to an object which can't be converted to the element type, with `==`,
+/// which e.g. finds `2.0` equal to `2`.
+fn py_equals<T: Primitive>(object: &PyAny, element: T) -> PyResult<bool> {
+    let py = object.py();
+    object
+        .rich_compare(element.into_py(py), CompareOp::Eq)?
+        .is_true(py)
+}
+
+/// Looks for `element` between `start` and `stop`. Elements are compared natively, unless
+/// `element` can't be converted to the element type, see `py_equals`.
+fn position<T: Primitive>(
+    vector: &rpds::VectorSync<T>,
+    element: &PyAny,
+    start: usize,
+    stop: usize,
+) -> PyResult<Option<usize>> {
+    let position = if let Ok(native) = element.extract::<T>() {
+        element.py().allow_threads(|| {
+            elements(vector, start, stop).position(|candidate| *candidate == native)
+        })
+    } else {
+        let mut found = None;
+        for (position, candidate) in elements(vector, start, stop).enumerate() {
+            if py_equals(element, *candidate)? {
+                found = Some(position);
+                break;
+            }
+        }
+        found
+    };
+    Ok(position.map(|position| start + position))
+}
+
+fn count<T: Primitive>(
+    vector: &rpds::VectorSync<T>,
+    element: &PyAny,
+    start: usize,
+    stop: usize,
+) -> PyResult<usize> {
+    if let Ok(native) = element.extract::<T>() {
+        return Ok(element.py().allow_threads(|| {
+            elements(vector, start, stop)
+                .filter(|candidate| **candidate == native)
+                .count()
+        }));
+    }
+
+    let mut count = 0;
+    for candidate in elements(vector, start, stop) {
+        if py_equals(element, *candidate)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn sum<T: Primitive>(py: Python, vector: &rpds::VectorSync<T>) -> T::Sum {
+    py.allow_threads(|| T::sum(vector.iter().copied()))
+}
+
+/// Removes the elements from `start` to `stop`, see `without_range`.
+fn splice<T: Primitive>(
+    py: Python,
+    vector: &rpds::VectorSync<T>,
+    start: usize,
+    stop: usize,
+) -> rpds::VectorSync<T> {
+    py.allow_threads(|| without_range(vector, start, stop))
+}
+
+fn push_back_all<T: Primitive>(vector: &mut rpds::VectorSync<T>, iterable: &PyAny) -> PyResult<()> {
+    for element in iterable.iter()? {
+        vector.push_back_mut(element?.extract::<T>()?);
+    }
+    Ok(())
+}
+
+impl<T: Primitive> Indexed for rpds::VectorSync<T> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn py_element(&self, index: usize) -> PyResult<PyObject> {
+        // Called by the iterator's `__next__`, hence with the GIL held.
+        Ok(self[index].into_py(unsafe { gil_held() }))
+    }
+}
+
+/// Copies the elements into a `memoryview` typed with the `struct` format of the elements,
+/// which `numpy` and `struct` based code can consume without a Python-level loop.
+fn to_buffer<T: Primitive>(py: Python, vector: &rpds::VectorSync<T>) -> PyResult<PyObject> {
+    let bytes = py.allow_threads(|| {
+        let mut bytes = Vec::with_capacity(vector.len() * mem::size_of::<T>());
+        for element in vector.iter() {
+            element.extend_ne_bytes(&mut bytes);
+        }
+        bytes
+    });
+
+    let memoryview = py.import("builtins")?.get("memoryview")?;
+    let buffer = memoryview
+        .call1((PyBytes::new(py, &bytes),))?
+        .call_method1("cast", (T::FORMAT,))?;
+    Ok(buffer.to_object(py))
+}
+
+fn is_native_byte_order(format: &[u8]) -> bool {
+    match format.first() {
+        Some(b'<') => cfg!(target_endian = "little"),
+        Some(b'>' | b'!') => cfg!(target_endian = "big"),
+        _ => true,
+    }
+}
+
+/// Reads the elements from a contiguous buffer, either of raw bytes in native byte order
+/// or of elements of the same type, such as the ones `to_buffer` returns.
+fn from_buffer<T: Primitive>(py: Python, object: &PyAny) -> PyResult<rpds::VectorSync<T>> {
+    let buffer = PyBuffer::get(py, object)?;
+
+    let element_type = ElementType::from_format(buffer.format());
+    let is_raw = match element_type {
+        ElementType::UnsignedInteger { bytes } | ElementType::SignedInteger { bytes } => bytes == 1,
+        _ => false,
+    };
+    if !buffer.is_c_contiguous()
+        || !is_native_byte_order(buffer.format().to_bytes())
+        || !(is_raw || element_type == T::ELEMENT_TYPE)
+    {
+        return Err(PyErr::new::<exceptions::TypeError, _>(format!(
+            "Expected a contiguous buffer of bytes or of '{}' elements!",
+            T::FORMAT
+        )));
+    }
+
+    let size = mem::size_of::<T>();
+    if buffer.len_bytes() % size != 0 {
+        return Err(PyErr::new::<exceptions::ValueError, _>(format!(
+            "Buffer size must be a multiple of {}!",
+            size
+        )));
+    }
+
+    // The exporter can't free, nor resize, the memory until the buffer is released. Other
+    // threads can still write to it as soon as the GIL is released, so it's copied beforehand.
+    let bytes =
+        unsafe { std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.len_bytes()) }
+            .to_vec();
+    buffer.release(py);
+
+    Ok(py.allow_threads(|| {
+        let mut vector = rpds::Vector::new_sync();
+        for element in bytes.chunks_exact(size) {
+            vector.push_back_mut(T::from_ne_bytes(element));
+        }
+        vector
+    }))
+}
+
+fn index_error(index: usize) -> PyErr {
+    PyErr::new::<exceptions::IndexError, _>(format!("Index out of range: {}", index))
+}
+
+fn not_found_error() -> PyErr {
+    PyErr::new::<exceptions::ValueError, _>("Element not in vector!")
+}
+
+macro_rules! primitive_vector {
+    ($struct_:ident, $element:ty, $constructor:ident) => {
+        #[pyclass]
+        #[derive(Default)]
+        pub struct $struct_ {
+            value: rpds::VectorSync<$element>,
+            hash: Cell<Option<isize>>,
+        }
+
+        impl $struct_ {
+            #[must_use]
+            pub fn new() -> Self {
+                Self::from(rpds::Vector::new_sync())
+            }
+
+            pub fn py_hash(&self) -> PyResult<isize> {
+                if let Some(hash) = self.hash.get() {
+                    return Ok(hash);
+                }
+
+                let value = &self.value;
+                let py = unsafe { gil_held() };
+                let hash = py.allow_threads(|| hash_elements(value));
+                self.hash.set(Some(hash));
+                Ok(hash)
+            }
+        }
+
+        impl From<rpds::VectorSync<$element>> for $struct_ {
+            fn from(value: rpds::VectorSync<$element>) -> Self {
+                $struct_ {
+                    value,
+                    hash: Cell::new(None),
+                }
+            }
+        }
+
+        impl Footprint for $struct_ {
+            fn add_cells(&self, usage: &mut MemoryUsage) {
+                usage.add_cells(
+                    self.value.iter(),
+                    cell_bytes::<$element>(),
+                    ESTIMATED_VECTOR_SLOT_BYTES,
+                );
+            }
+        }
+
+        #[pymethods]
+        impl $struct_ {
+            pub fn set(&self, index: isize, element: $element) -> PyResult<Self> {
+                let index = normalize_index(self.value.len(), index)?;
+                let new_value = if index == self.value.len() {
+                    Some(self.value.push_back(element))
+                } else {
+                    self.value.set(index, element)
+                };
+
+                new_value.map(Self::from).ok_or_else(|| index_error(index))
+            }
+
+            pub fn append(&self, element: $element) -> PyResult<Self> {
+                Ok(Self::from(self.value.push_back(element)))
+            }
+
+            pub fn extend(&self, iterable: &PyAny) -> PyResult<Self> {
+                let mut value = self.value.clone();
+                push_back_all(&mut value, iterable)?;
+                Ok(Self::from(value))
+            }
+
+            pub fn get(&self, py: Python, index: isize) -> PyResult<PyObject> {
+                let index = normalize_index(self.value.len(), index)?;
+                match self.value.get(index) {
+                    Some(element) => Ok((*element).into_py(py)),
+                    None => Err(index_error(index)),
+                }
+            }
+
+            #[args(start = "0", stop = "None")]
+            pub fn index(
+                &self,
+                element: &PyAny,
+                start: isize,
+                stop: Option<isize>,
+            ) -> PyResult<usize> {
+                let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+                position(&self.value, element, start, stop)?.ok_or_else(not_found_error)
+            }
+
+            #[args(start = "0", stop = "None")]
+            pub fn count(
+                &self,
+                element: &PyAny,
+                start: isize,
+                stop: Option<isize>,
+            ) -> PyResult<usize> {
+                let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+                count(&self.value, element, start, stop)
+            }
+
+            pub fn remove(&self, py: Python, element: &PyAny) -> PyResult<Self> {
+                let position = position(&self.value, element, 0, self.value.len())?
+                    .ok_or_else(not_found_error)?;
+                Ok(Self::from(splice(py, &self.value, position, position + 1)))
+            }
+
+            #[args(stop = "None")]
+            pub fn delete(&self, py: Python, index: isize, stop: Option<isize>) -> PyResult<Self> {
+                if let Some(stop) = stop {
+                    let (start, stop) = normalize_range(self.value.len(), index, Some(stop))?;
+                    return Ok(Self::from(splice(py, &self.value, start, stop.max(start))));
+                }
+
+                let index = normalize_index(self.value.len(), index)?;
+                if index >= self.value.len() {
+                    return Err(index_error(index));
+                }
+                Ok(Self::from(splice(py, &self.value, index, index + 1)))
+            }
+
+            #[args(args = "*")]
+            pub fn mset(&self, args: &PyTuple) -> PyResult<Self> {
+                if args.len() % 2 != 0 {
+                    return Err(PyErr::new::<exceptions::TypeError, _>(
+                        "Not enough arguments!",
+                    ));
+                }
+
+                let mut vector = Self::from(self.value.clone());
+                for arg_index in (0..args.len()).step_by(2) {
+                    let index = args.get_item(arg_index).extract::<isize>()?;
+                    let element = args.get_item(arg_index + 1).extract::<$element>()?;
+                    vector = vector.set(index, element)?;
+                }
+                Ok(vector)
+            }
+
+            pub fn to_buffer(&self, py: Python) -> PyResult<PyObject> {
+                to_buffer(py, &self.value)
+            }
+
+            #[staticmethod]
+            pub fn from_buffer(py: Python, buffer: &PyAny) -> PyResult<Self> {
+                Ok(Self::from(from_buffer(py, buffer)?))
+            }
+
+            pub fn sum(&self, py: Python) -> PyResult<PyObject> {
+                Ok(sum(py, &self.value).into_py(py))
+            }
+
+            #[must_use]
+            #[allow(clippy::needless_pass_by_value)]
+            pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+                Self::size_of(&slf)
+            }
+
+            #[must_use]
+            pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+                slf.into()
+            }
+
+            /// The elements are immutable values, so even a deep copy can share them.
+            #[must_use]
+            #[allow(clippy::needless_pass_by_value)]
+            pub fn __deepcopy__(slf: PyRef<Self>, _memo: PyObject) -> Py<Self> {
+                slf.into()
+            }
+
+            pub fn __reversed__(&self) -> PyResult<PyObjectIterator> {
+                Ok(PyObjectIterator::new(
+                    VectorCursor::new(self.value.clone()).rev(),
+                ))
+            }
+        }
+
+        #[pyproto]
+        impl PySequenceProtocol for $struct_ {
+            fn __len__(&self) -> PyResult<usize> {
+                Ok(self.value.len())
+            }
+
+            fn __getitem__(&self, index: isize) -> PyResult<PyObject> {
+                // pyo3 already added the length to negative indices, see `Vector::__getitem__`.
+                if index < 0 {
+                    let original_index = index - isize::try_from(self.value.len())?;
+                    return Err(PyErr::new::<exceptions::IndexError, _>(format!(
+                        "Index out of range: {}",
+                        original_index
+                    )));
+                }
+                self.get(unsafe { gil_held() }, index)
+            }
+
+            fn __contains__(&self, element: &PyAny) -> PyResult<bool> {
+                Ok(position(&self.value, element, 0, self.value.len())?.is_some())
+            }
+        }
+
+        #[pyproto]
+        impl PyIterProtocol for $struct_ {
+            fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectIterator> {
+                Ok(PyObjectIterator::new(VectorCursor::new(slf.value.clone())))
+            }
+        }
+
+        py_object_protocol!($struct_);
+
+        impl std::fmt::Display for $struct_ {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}([", stringify!($constructor))?;
+                for (index, element) in self.value.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    // Formatted by Python, so that e.g. floats look the same as in a `pvector`.
+                    write!(
+                        f,
+                        "{}",
+                        Object::new((*element).into_py(unsafe { gil_held() }))
+                    )?;
+                }
+                write!(f, "])")
+            }
+        }
+
+        #[pyfunction(args = "*")]
+        fn $constructor(args: &PyTuple) -> PyResult<$struct_> {
+            let mut value = rpds::Vector::new_sync();
+            if args.is_empty() {
+                return Ok($struct_::from(value));
+            } else if args.len() > 1 {
+                return Err(PyErr::new::<exceptions::ValueError, _>(
+                    "Incorrect number of arguments!!",
+                ));
+            }
+
+            push_back_all(&mut value, args.get_item(0))?;
+            Ok($struct_::from(value))
+        }
+    };
+}
+
+primitive_vector!(I64Vector, i64, pvector_i64);
+primitive_vector!(F64Vector, f64, pvector_f64);
+primitive_vector!(BoolVector, bool, pvector_bool);
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<I64Vector>()?;
+    m.add_class::<F64Vector>()?;
+    m.add_class::<BoolVector>()?;
+    m.add_wrapped(wrap_pyfunction!(pvector_i64)).unwrap();
+    m.add_wrapped(wrap_pyfunction!(pvector_f64)).unwrap();
+    m.add_wrapped(wrap_pyfunction!(pvector_bool)).unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,667 @@
+//! A persistent relaxed radix balanced (RRB) vector.
+//!
+//! Like `rpds::Vector`, the elements are stored in the leaves of a trie with up to `BRANCHING`
+//! children per node. Unlike it, the nodes don't have to be full: every branch keeps the
+//! cumulative sizes of its children, so that two tries can be joined, and a trie can be cut,
+//! by only rebuilding the nodes along the seams. Concatenation, slicing, insertion and removal
+//! at any index are therefore logarithmic.
+//!
+//! When a concatenation leaves more than `EXTRA_NODES` nodes above the optimal number on some
+//! level, their slots are redistributed, which keeps the height of the trie logarithmic.
+
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::rc::Rc;
+
+use crate::memory::cell_bytes;
+
+const BRANCHING: usize = 32;
+
+// The number of nodes tolerated per level, on top of the optimal number, after a concatenation.
+const EXTRA_NODES: usize = 2;
+
+enum Node<T> {
+    Leaf(Vec<T>),
+    Branch(Branch<T>),
+}
+
+struct Branch<T> {
+    // The height of the leaves is 0, all the children of a branch have the same height.
+    height: usize,
+    children: Vec<Rc<Node<T>>>,
+    // `sizes[i]` is the number of elements in `children[..=i]`.
+    sizes: Vec<usize>,
+}
+
+impl<T> Node<T> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(elements) => elements.len(),
+            Node::Branch(branch) => branch.sizes.last().copied().unwrap_or(0),
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Branch(branch) => branch.height,
+        }
+    }
+
+    /// The bytes of the node: its reference counted allocation and the buffers it owns.
+    fn bytes(&self) -> usize {
+        let buffers = match self {
+            Node::Leaf(elements) => elements.capacity() * mem::size_of::<T>(),
+            Node::Branch(branch) => {
+                branch.children.capacity() * mem::size_of::<Rc<Node<T>>>()
+                    + branch.sizes.capacity() * mem::size_of::<usize>()
+            }
+        };
+        cell_bytes::<Node<T>>() + buffers
+    }
+
+    /// The number of elements of a leaf, or of children of a branch.
+    fn slots(&self) -> usize {
+        match self {
+            Node::Leaf(elements) => elements.len(),
+            Node::Branch(branch) => branch.children.len(),
+        }
+    }
+}
+
+impl<T> Branch<T> {
+    fn new(children: Vec<Rc<Node<T>>>) -> Self {
+        let height = children[0].height() + 1;
+        let sizes = children
+            .iter()
+            .scan(0, |total, child| {
+                *total += child.len();
+                Some(*total)
+            })
+            .collect();
+
+        Branch {
+            height,
+            children,
+            sizes,
+        }
+    }
+
+    /// Returns the child holding the element at `index`, along with the number of elements
+    /// in the children before it.
+    fn locate(&self, index: usize) -> (usize, usize) {
+        // The sizes are strictly increasing, the child is the first one whose size exceeds `index`.
+        let child = match self.sizes.binary_search(&index) {
+            Ok(child) => child + 1,
+            Err(child) => child,
+        };
+        let offset = if child == 0 { 0 } else { self.sizes[child - 1] };
+        (child, offset)
+    }
+}
+
+fn branch<T>(children: Vec<Rc<Node<T>>>) -> Rc<Node<T>> {
+    Rc::new(Node::Branch(Branch::new(children)))
+}
+
+pub struct Vector<T> {
+    root: Rc<Node<T>>,
+}
+
+/// A node reached by `Vector::for_each_node`.
+pub struct NodeInfo {
+    pub address: usize,
+    // The root is on level 1.
+    pub level: usize,
+    pub bytes: usize,
+}
+
+impl<T> Clone for Vector<T> {
+    fn clone(&self) -> Self {
+        Vector {
+            root: Rc::clone(&self.root),
+        }
+    }
+}
+
+impl<T> Default for Vector<T> {
+    fn default() -> Self {
+        Vector {
+            root: Rc::new(Node::Leaf(Vec::new())),
+        }
+    }
+}
+
+impl<T: Clone> Vector<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a trie whose root may be a chain of branches with a single child.
+    fn from_root(mut root: Rc<Node<T>>) -> Self {
+        while let Node::Branch(branch) = &*root {
+            if branch.children.len() != 1 {
+                break;
+            }
+            root = Rc::clone(&branch.children[0]);
+        }
+        Vector { root }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut node = &*self.root;
+        let mut index = index;
+        loop {
+            match node {
+                Node::Leaf(elements) => return elements.get(index),
+                Node::Branch(branch) => {
+                    if index >= node.len() {
+                        return None;
+                    }
+                    let (child, offset) = branch.locate(index);
+                    node = &branch.children[child];
+                    index -= offset;
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn set(&self, index: usize, element: T) -> Option<Self> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(Vector {
+            root: set(&self.root, index, element),
+        })
+    }
+
+    #[must_use]
+    pub fn push_back(&self, element: T) -> Self {
+        match push_back(&self.root, element) {
+            (root, None) => Vector { root },
+            (root, Some(sibling)) => Vector {
+                root: branch(vec![root, sibling]),
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn concat(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self.clone();
+        }
+
+        let mut nodes = concat(&self.root, &other.root);
+        if nodes.len() == 1 {
+            return Self::from_root(nodes.remove(0));
+        }
+        Vector {
+            root: branch(nodes),
+        }
+    }
+
+    /// Keeps the first `count` elements.
+    #[must_use]
+    pub fn take(&self, count: usize) -> Self {
+        if count >= self.len() {
+            return self.clone();
+        }
+        if count == 0 {
+            return Self::new();
+        }
+        Self::from_root(take(&self.root, count))
+    }
+
+    /// Drops the first `count` elements.
+    #[must_use]
+    pub fn skip(&self, count: usize) -> Self {
+        if count >= self.len() {
+            return Self::new();
+        }
+        Self::from_root(skip(&self.root, count))
+    }
+
+    /// Returns the elements from `start` to `stop`.
+    #[must_use]
+    pub fn slice(&self, start: usize, stop: usize) -> Self {
+        self.skip(start).take(stop.saturating_sub(start))
+    }
+
+    #[must_use]
+    pub fn insert(&self, index: usize, element: T) -> Self {
+        self.take(index)
+            .push_back(element)
+            .concat(&self.skip(index))
+    }
+
+    /// Removes the elements from `start` to `stop`.
+    #[must_use]
+    pub fn remove_range(&self, start: usize, stop: usize) -> Self {
+        self.take(start).concat(&self.skip(stop))
+    }
+
+    /// Calls `visit` with every node reachable from the root. The children of a node are only
+    /// visited if `visit` returns `true`, which lets a node shared by several branches, or by
+    /// several vectors, be walked once.
+    pub fn for_each_node<F: FnMut(NodeInfo) -> bool>(&self, mut visit: F) {
+        let mut pending = vec![(&self.root, 1)];
+        while let Some((node, level)) = pending.pop() {
+            let node: &Node<T> = node;
+            let address: *const Node<T> = node;
+            let info = NodeInfo {
+                address: address as usize,
+                level,
+                bytes: node.bytes(),
+            };
+            if !visit(info) {
+                continue;
+            }
+            if let Node::Branch(branch) = node {
+                pending.extend(branch.children.iter().map(|child| (child, level + 1)));
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(&self.root)
+    }
+
+    /// Iterates from the element at `index`, which is reached by descending the trie rather
+    /// than by walking the elements before it.
+    #[must_use]
+    pub fn iter_from(&self, index: usize) -> Iter<'_, T> {
+        Iter::starting_at(&self.root, index)
+    }
+}
+
+impl<T: Clone> std::iter::FromIterator<T> for Vector<T> {
+    /// Fills the leaves one after the other, then builds the branches level by level.
+    fn from_iter<I: IntoIterator<Item = T>>(iterable: I) -> Self {
+        let mut nodes = Vec::new();
+        let mut elements = Vec::with_capacity(BRANCHING);
+        for element in iterable {
+            elements.push(element);
+            if elements.len() == BRANCHING {
+                let leaf = std::mem::replace(&mut elements, Vec::with_capacity(BRANCHING));
+                nodes.push(Rc::new(Node::Leaf(leaf)));
+            }
+        }
+        if !elements.is_empty() {
+            nodes.push(Rc::new(Node::Leaf(elements)));
+        }
+
+        if nodes.is_empty() {
+            return Self::new();
+        }
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(BRANCHING)
+                .map(|children| branch(children.to_vec()))
+                .collect();
+        }
+        Vector {
+            root: nodes.remove(0),
+        }
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a Vector<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Vector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.root, &other.root)
+            || (self.root.len() == other.root.len()
+                && Iter::new(&self.root).eq(Iter::new(&other.root)))
+    }
+}
+
+impl<T: Hash> Hash for Vector<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.root.len().hash(state);
+        for element in Iter::new(&self.root) {
+            element.hash(state);
+        }
+    }
+}
+
+fn set<T: Clone>(node: &Rc<Node<T>>, index: usize, element: T) -> Rc<Node<T>> {
+    match &**node {
+        Node::Leaf(elements) => {
+            let mut elements = elements.clone();
+            elements[index] = element;
+            Rc::new(Node::Leaf(elements))
+        }
+        Node::Branch(branch) => {
+            let (child, offset) = branch.locate(index);
+            let mut children = branch.children.clone();
+            children[child] = set(&branch.children[child], index - offset, element);
+            Rc::new(Node::Branch(Branch {
+                height: branch.height,
+                children,
+                sizes: branch.sizes.clone(),
+            }))
+        }
+    }
+}
+
+/// Appends an element to the last leaf. Returns the new node, along with a new sibling
+/// of the same height if the node was full.
+fn push_back<T: Clone>(node: &Rc<Node<T>>, element: T) -> (Rc<Node<T>>, Option<Rc<Node<T>>>) {
+    match &**node {
+        Node::Leaf(elements) if elements.len() < BRANCHING => {
+            let mut elements = elements.clone();
+            elements.push(element);
+            (Rc::new(Node::Leaf(elements)), None)
+        }
+        Node::Leaf(_) => (Rc::clone(node), Some(Rc::new(Node::Leaf(vec![element])))),
+        Node::Branch(branch) => {
+            let mut children = branch.children.clone();
+            let last = children.pop().expect("branches have children");
+            let (last, sibling) = push_back(&last, element);
+            children.push(last);
+
+            match sibling {
+                None => (self::branch(children), None),
+                Some(sibling) if children.len() < BRANCHING => {
+                    children.push(sibling);
+                    (self::branch(children), None)
+                }
+                Some(sibling) => (self::branch(children), Some(self::branch(vec![sibling]))),
+            }
+        }
+    }
+}
+
+fn take<T: Clone>(node: &Rc<Node<T>>, count: usize) -> Rc<Node<T>> {
+    if count == node.len() {
+        return Rc::clone(node);
+    }
+
+    match &**node {
+        Node::Leaf(elements) => Rc::new(Node::Leaf(elements[..count].to_vec())),
+        Node::Branch(branch) => {
+            let (child, offset) = branch.locate(count - 1);
+            let mut children = branch.children[..child].to_vec();
+            children.push(take(&branch.children[child], count - offset));
+            self::branch(children)
+        }
+    }
+}
+
+fn skip<T: Clone>(node: &Rc<Node<T>>, count: usize) -> Rc<Node<T>> {
+    if count == 0 {
+        return Rc::clone(node);
+    }
+
+    match &**node {
+        Node::Leaf(elements) => Rc::new(Node::Leaf(elements[count..].to_vec())),
+        Node::Branch(branch) => {
+            let (child, offset) = branch.locate(count);
+            let mut children = vec![skip(&branch.children[child], count - offset)];
+            children.extend(branch.children[child + 1..].iter().cloned());
+            self::branch(children)
+        }
+    }
+}
+
+/// Joins two non-empty tries. Returns one or two nodes as high as the highest of the two.
+fn concat<T: Clone>(left: &Rc<Node<T>>, right: &Rc<Node<T>>) -> Vec<Rc<Node<T>>> {
+    match (&**left, &**right) {
+        (Node::Leaf(_), Node::Leaf(_)) => rebalance(vec![Rc::clone(left), Rc::clone(right)]),
+        (Node::Branch(left_branch), _) if left.height() > right.height() => {
+            let (last, init) = left_branch
+                .children
+                .split_last()
+                .expect("branches have children");
+            let mut nodes = init.to_vec();
+            nodes.extend(concat(last, right));
+            pack(rebalance(nodes))
+        }
+        (_, Node::Branch(right_branch)) if left.height() < right.height() => {
+            let (first, tail) = right_branch
+                .children
+                .split_first()
+                .expect("branches have children");
+            let mut nodes = concat(left, first);
+            nodes.extend(tail.iter().cloned());
+            pack(rebalance(nodes))
+        }
+        (Node::Branch(left_branch), Node::Branch(right_branch)) => {
+            let (last, init) = left_branch
+                .children
+                .split_last()
+                .expect("branches have children");
+            let (first, tail) = right_branch
+                .children
+                .split_first()
+                .expect("branches have children");
+            let mut nodes = init.to_vec();
+            nodes.extend(concat(last, first));
+            nodes.extend(tail.iter().cloned());
+            pack(rebalance(nodes))
+        }
+        _ => unreachable!("a leaf is lower than any branch"),
+    }
+}
+
+/// Groups nodes of the same height into one or two branches.
+fn pack<T>(mut nodes: Vec<Rc<Node<T>>>) -> Vec<Rc<Node<T>>> {
+    if nodes.len() <= BRANCHING {
+        return vec![branch(nodes)];
+    }
+    let rest = nodes.split_off(BRANCHING);
+    vec![branch(nodes), branch(rest)]
+}
+
+/// Redistributes the slots of nodes of the same height over fewer nodes when there are
+/// more than `EXTRA_NODES` nodes above the optimal number. Only the nodes whose slots change
+/// are rebuilt, the others are kept as they are.
+fn rebalance<T: Clone>(nodes: Vec<Rc<Node<T>>>) -> Vec<Rc<Node<T>>> {
+    let slots: Vec<usize> = nodes.iter().map(|node| node.slots()).collect();
+    match plan(&slots) {
+        Some(plan) => regroup(&nodes, &slots, &plan),
+        None => nodes,
+    }
+}
+
+fn regroup<T: Clone>(nodes: &[Rc<Node<T>>], slots: &[usize], plan: &[usize]) -> Vec<Rc<Node<T>>> {
+    let mut regrouped = Vec::with_capacity(plan.len());
+    let mut start = 0;
+    let mut original_start = 0;
+    let mut original = 0;
+    for size in plan {
+        while original_start < start {
+            original_start += slots[original];
+            original += 1;
+        }
+        if original_start == start && original < nodes.len() && slots[original] == *size {
+            regrouped.push(Rc::clone(&nodes[original]));
+        } else {
+            regrouped.push(gather(nodes, start, *size));
+        }
+        start += size;
+    }
+    regrouped
+}
+
+/// Plans the number of slots of every node after a concatenation, following the RRB paper:
+/// the first node that isn't almost full is emptied into the nodes after it, until the number
+/// of nodes is close enough to the optimal one. Returns `None` if it already is.
+fn plan(slots: &[usize]) -> Option<Vec<usize>> {
+    let total: usize = slots.iter().sum();
+    let full_nodes = total / BRANCHING;
+    let optimal = if full_nodes * BRANCHING < total {
+        full_nodes + 1
+    } else {
+        full_nodes
+    };
+    if slots.len() <= optimal + EXTRA_NODES {
+        return None;
+    }
+
+    let mut plan = slots.to_vec();
+    let mut index = 0;
+    while plan.len() > optimal + EXTRA_NODES {
+        while index < plan.len() && plan[index] >= BRANCHING - EXTRA_NODES / 2 {
+            index += 1;
+        }
+        if index >= plan.len() - 1 {
+            break;
+        }
+
+        let mut remaining = plan[index];
+        let mut next = index + 1;
+        while remaining > 0 && next < plan.len() {
+            let moved = remaining.min(BRANCHING - plan[next]);
+            plan[next] += moved;
+            remaining -= moved;
+            next += 1;
+        }
+        if remaining > 0 {
+            plan[index] = remaining;
+            break;
+        }
+        plan.remove(index);
+    }
+    Some(plan)
+}
+
+/// Builds a node from `size` slots, starting at `start`, of the slots of `nodes` one after the other.
+fn gather<T: Clone>(nodes: &[Rc<Node<T>>], start: usize, size: usize) -> Rc<Node<T>> {
+    match &*nodes[0] {
+        Node::Leaf(_) => {
+            let elements = nodes
+                .iter()
+                .flat_map(|node| match &**node {
+                    Node::Leaf(elements) => elements.iter(),
+                    Node::Branch(_) => unreachable!("the nodes have the same height"),
+                })
+                .skip(start)
+                .take(size)
+                .cloned()
+                .collect();
+            Rc::new(Node::Leaf(elements))
+        }
+        Node::Branch(_) => {
+            let children = nodes
+                .iter()
+                .flat_map(|node| match &**node {
+                    Node::Branch(branch) => branch.children.iter(),
+                    Node::Leaf(_) => unreachable!("the nodes have the same height"),
+                })
+                .skip(start)
+                .take(size)
+                .cloned()
+                .collect();
+            branch(children)
+        }
+    }
+}
+
+/// Walks over the leaves from left to right.
+pub struct Iter<'a, T> {
+    branches: Vec<std::slice::Iter<'a, Rc<Node<T>>>>,
+    leaf: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn new(root: &'a Node<T>) -> Self {
+        let mut iter = Iter {
+            branches: Vec::new(),
+            leaf: [].iter(),
+        };
+        iter.descend(root);
+        iter
+    }
+
+    fn starting_at(root: &'a Node<T>, index: usize) -> Self {
+        let mut iter = Iter {
+            branches: Vec::new(),
+            leaf: [].iter(),
+        };
+        if index >= root.len() {
+            return iter;
+        }
+
+        let mut node = root;
+        let mut index = index;
+        loop {
+            match node {
+                Node::Leaf(elements) => {
+                    iter.leaf = elements[index..].iter();
+                    return iter;
+                }
+                Node::Branch(branch) => {
+                    let (child, offset) = branch.locate(index);
+                    let mut children = branch.children[child..].iter();
+                    node = children.next().expect("the index is in range");
+                    iter.branches.push(children);
+                    index -= offset;
+                }
+            }
+        }
+    }
+
+    fn descend(&mut self, node: &'a Node<T>) {
+        let mut node = node;
+        loop {
+            match node {
+                Node::Leaf(elements) => {
+                    self.leaf = elements.iter();
+                    return;
+                }
+                Node::Branch(branch) => {
+                    let mut children = branch.children.iter();
+                    node = children.next().expect("branches have children");
+                    self.branches.push(children);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.leaf.next() {
+                return Some(element);
+            }
+
+            let child = loop {
+                match self.branches.last_mut()?.next() {
+                    Some(child) => break child,
+                    None => {
+                        self.branches.pop();
+                    }
+                }
+            };
+            self.descend(child);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::cell::Cell;
+use std::convert::TryFrom;
+
+use pyo3::class::{PyMappingProtocol, PyNumberProtocol, PyObjectProtocol, PySequenceProtocol};
+use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::types::{PySlice, PyTuple};
+use pyo3::{
+    exceptions, wrap_pyfunction, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol,
+    PyNativeType, PyRef, PyRefMut, Python, ToPyObject,
+};
+
+use crate::debug::{Layout, Nodes};
+use crate::hashing::ordered_hash;
+use crate::memory::{Footprint, MemoryUsage};
+use crate::object::{catch, deepcopy_object, extract_py_object, Object};
+use crate::vector::{normalize_index, normalize_range};
+
+type RrbTree = crate::rrb::Vector<Object>;
+
+/// A vector backed by a relaxed radix balanced trie, see `rrb`. On top of the operations of
+/// `Vector`, concatenation, slicing, and insertion or deletion at any index are logarithmic.
+#[pyclass]
+#[derive(Default)]
+pub struct RrbVector {
+    value: RrbTree,
+    hash: Cell<Option<isize>>,
+}
+
+impl RrbVector {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from(RrbTree::new())
+    }
+
+    /// The hash is computed once, the elements of a vector can't change.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
+        }
+
+        let hash = ordered_hash(&self.value)?;
+        self.hash.set(Some(hash));
+        Ok(hash)
+    }
+
+    fn position(&self, object: &Object, start: usize, stop: usize) -> PyResult<Option<usize>> {
+        catch(|| {
+            self.value
+                .iter_from(start)
+                .take(stop.saturating_sub(start))
+                .position(|element| element == object)
+                .map(|position| start + position)
+        })
+    }
+
+    fn slice(&self, py: Python, slice: &PySlice) -> PyResult<PyObject> {
+        let length = self.value.len();
+        #[allow(clippy::cast_possible_wrap)]
+        let indices = slice.indices(length as _)?;
+        let start = usize::try_from(indices.start).unwrap_or(0);
+        let slice_length = usize::try_from(indices.slicelength)?;
+
+        let value = if indices.step == 1 {
+            self.value.slice(start, start + slice_length)
+        } else {
+            (0..slice_length)
+                .filter_map(|position| {
+                    let index = indices.start + isize::try_from(position).ok()? * indices.step;
+                    self.value.get(usize::try_from(index).ok()?).cloned()
+                })
+                .collect()
+        };
+        Ok(Py::new(py, Self::from(value))?.to_object(py))
+    }
+}
+
+impl From<RrbTree> for RrbVector {
+    fn from(value: RrbTree) -> Self {
+        RrbVector {
+            value,
+            hash: Cell::new(None),
+        }
+    }
+}
+
+impl Layout for RrbVector {
+    fn nodes(&self) -> Nodes {
+        let mut nodes = Nodes::new();
+        self.value
+            .for_each_node(|node| nodes.insert(node.address, node.level).is_none());
+        nodes
+    }
+}
+
+impl Footprint for RrbVector {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        // The elements are stored in the leaves, their bytes are those of the leaf.
+        self.value
+            .for_each_node(|node| usage.add_node(node.address, node.bytes));
+    }
+}
+
+fn index_error(index: usize) -> PyErr {
+    PyErr::new::<exceptions::IndexError, _>(format!("Index out of range: {}", index))
+}
+
+fn not_found_error() -> PyErr {
+    PyErr::new::<exceptions::ValueError, _>("Element not in vector!")
+}
+
+fn collect(iterable: &PyAny) -> PyResult<RrbTree> {
+    iterable
+        .iter()?
+        .map(|element| Ok(Object::new(element?.extract::<PyObject>()?)))
+        .collect()
+}
+
+#[pymethods]
+impl RrbVector {
+    pub fn set(&self, index: isize, py_object: PyObject) -> PyResult<Self> {
+        let index = normalize_index(self.value.len(), index)?;
+
+        let object = Object::new(py_object);
+        if index == self.value.len() {
+            return Ok(Self::from(self.value.push_back(object)));
+        }
+        self.value
+            .set(index, object)
+            .map(Self::from)
+            .ok_or_else(|| index_error(index))
+    }
+
+    pub fn append(&self, py_object: PyObject) -> PyResult<Self> {
+        Ok(Self::from(self.value.push_back(Object::new(py_object))))
+    }
+
+    pub fn extend(&self, iterable: &PyAny) -> PyResult<Self> {
+        Ok(Self::from(self.value.concat(&collect(iterable)?)))
+    }
+
+    /// Inserts an element before `index`, which is clamped to the vector like in `list.insert`.
+    pub fn insert(&self, index: isize, py_object: PyObject) -> PyResult<Self> {
+        let (index, _) = normalize_range(self.value.len(), index, None)?;
+        Ok(Self::from(self.value.insert(index, Object::new(py_object))))
+    }
+
+    pub fn get(&self, index: isize) -> PyResult<PyObject> {
+        let index = normalize_index(self.value.len(), index)?;
+        match self.value.get(index) {
+            Some(element) => extract_py_object(Some(element)),
+            None => Err(index_error(index)),
+        }
+    }
+
+    #[args(start = "0", stop = "None")]
+    pub fn index(&self, py_object: PyObject, start: isize, stop: Option<isize>) -> PyResult<usize> {
+        let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+        self.position(&Object::new(py_object), start, stop)?
+            .ok_or_else(not_found_error)
+    }
+
+    #[args(start = "0", stop = "None")]
+    pub fn count(&self, py_object: PyObject, start: isize, stop: Option<isize>) -> PyResult<usize> {
+        let object = Object::new(py_object);
+        let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+
+        catch(|| {
+            self.value
+                .iter_from(start)
+                .take(stop.saturating_sub(start))
+                .filter(|element| **element == object)
+                .count()
+        })
+    }
+
+    pub fn remove(&self, py_object: PyObject) -> PyResult<Self> {
+        let position = self
+            .position(&Object::new(py_object), 0, self.value.len())?
+            .ok_or_else(not_found_error)?;
+        Ok(Self::from(self.value.remove_range(position, position + 1)))
+    }
+
+    #[args(stop = "None")]
+    pub fn delete(&self, index: isize, stop: Option<isize>) -> PyResult<Self> {
+        if let Some(stop) = stop {
+            let (start, stop) = normalize_range(self.value.len(), index, Some(stop))?;
+            return Ok(Self::from(self.value.remove_range(start, stop.max(start))));
+        }
+
+        let index = normalize_index(self.value.len(), index)?;
+        if index >= self.value.len() {
+            return Err(index_error(index));
+        }
+        Ok(Self::from(self.value.remove_range(index, index + 1)))
+    }
+
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
+
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut copied = false;
+        let mut elements = Vec::with_capacity(slf.value.len());
+        for element in slf.value.iter() {
+            match deepcopy_object(py, element, &memo)? {
+                Some(object) => {
+                    elements.push(object);
+                    copied = true;
+                }
+                None => elements.push(element.clone()),
+            }
+        }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+        Py::new(py, Self::from(elements.into_iter().collect::<RrbTree>()))
+    }
+
+    pub fn __reversed__(&self) -> PyResult<crate::iterators::PyObjectIterator> {
+        Ok(crate::iterators::PyObjectIterator::new(
+            crate::iterators::VectorCursor::new(self.value.clone()).rev(),
+        ))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for RrbVector {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.len())
+    }
+
+    fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
+        let object = Object::new(py_object);
+        Ok(self.position(&object, 0, self.value.len())?.is_some())
+    }
+}
+
+#[pyproto]
+impl PyMappingProtocol for RrbVector {
+    fn __getitem__(&self, key: &PyAny) -> PyResult<PyObject> {
+        if let Ok(slice) = key.downcast::<PySlice>() {
+            return self.slice(key.py(), slice);
+        }
+        self.get(key.extract::<isize>()?)
+    }
+}
+
+#[pyproto]
+impl PyNumberProtocol for RrbVector {
+    /// Concatenates two vectors in logarithmic time, or extends a vector with an iterable.
+    fn __add__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        let py = lhs.py();
+        let lhs = match lhs.downcast::<PyCell<RrbVector>>() {
+            Ok(lhs) => lhs.borrow(),
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+
+        let value = match rhs.downcast::<PyCell<RrbVector>>() {
+            Ok(rhs) => lhs.value.concat(&rhs.borrow().value),
+            Err(_) => lhs.value.concat(&collect(rhs)?),
+        };
+        Ok(Py::new(py, Self::from(value))?.to_object(py))
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for RrbVector {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<crate::iterators::PyObjectIterator> {
+        Ok(crate::iterators::PyObjectIterator::new(
+            crate::iterators::VectorCursor::new(slf.value.clone()),
+        ))
+    }
+}
+
+py_object_protocol!(RrbVector);
+
+impl std::fmt::Display for RrbVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pvector_rrb([")?;
+        for (index, element) in self.value.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", element)?;
+        }
+        write!(f, "])")
+    }
+}
+
+#[pyfunction(args = "*")]
+fn pvector_rrb(args: &PyTuple) -> PyResult<RrbVector> {
+    if args.is_empty() {
+        return Ok(RrbVector::new());
+    } else if args.len() > 1 {
+        return Err(PyErr::new::<exceptions::ValueError, _>(
+            "Incorrect number of arguments!!",
+        ));
+    }
+
+    Ok(RrbVector::from(collect(args.get_item(0))?))
+}
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<RrbVector>()?;
+    m.add_wrapped(wrap_pyfunction!(pvector_rrb)).unwrap();
+
+    Ok(())
+}
@@ -1,14 +1,17 @@
-use std::hash::{Hash, Hasher};
+use std::cell::Cell;
 
 use pyo3::class::{PyObjectProtocol, PySequenceProtocol};
 use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
-use pyo3::types::PyTuple;
+use pyo3::types::{PyDict, PyTuple};
 use pyo3::{
-    exceptions, wrap_pyfunction, AsPyRef, ObjectProtocol, PyAny, PyCell, PyErr, PyIterProtocol,
-    PyRefMut, Python,
+    exceptions, wrap_pyfunction, AsPyRef, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol,
+    PyRef, PyRefMut, Python, ToPyObject,
 };
 
-use crate::object::{extract_py_object, Object};
+use crate::hashing::{frozenset_finish, frozenset_state, frozenset_toggle};
+use crate::map::checked_degree;
+use crate::memory::{cell_bytes, Footprint, MemoryUsage, ESTIMATED_HASH_TRIE_NODE_BYTES};
+use crate::object::{catch, deepcopy_object, extract_py_object, keyword_only_argument, Object};
 
 type RpdsSet = rpds::HashTrieSet<Object>;
 
@@ -16,85 +19,162 @@ type RpdsSet = rpds::HashTrieSet<Object>;
 #[derive(Default)]
 pub struct Set {
     value: RpdsSet,
+    // The `frozenset_state` of the elements, once computed.
+    hash_state: Cell<Option<usize>>,
 }
 
 impl Set {
     #[must_use]
     pub fn new() -> Self {
+        Self::from(RpdsSet::new())
+    }
+
+    /// Hashes the set the same way as a `frozenset` with the same elements.
+    /// The hash is computed once and carried over to the sets derived from this one.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        Ok(frozenset_finish(self.elements_state()?, self.value.size()))
+    }
+
+    fn elements_state(&self) -> PyResult<usize> {
+        if let Some(state) = self.hash_state.get() {
+            return Ok(state);
+        }
+
+        let hashes = self
+            .value
+            .iter()
+            .map(Object::py_hash)
+            .collect::<PyResult<Vec<_>>>()?;
+        let state = frozenset_state(hashes);
+        self.hash_state.set(Some(state));
+        Ok(state)
+    }
+
+    /// Derives a set from this one by adding or removing `object`, updating the cached hash
+    /// when the size changed. The cache is dropped if `object` can't be hashed.
+    fn derive(&self, value: RpdsSet, object: &Object) -> Self {
+        let hash_state = match self.hash_state.get() {
+            Some(state) if value.size() != self.value.size() => object
+                .py_hash()
+                .ok()
+                .map(|element_hash| frozenset_toggle(state, element_hash)),
+            state => state,
+        };
+
         Set {
-            value: RpdsSet::new(),
+            value,
+            hash_state: Cell::new(hash_state),
         }
     }
 }
 
+impl From<RpdsSet> for Set {
+    fn from(value: RpdsSet) -> Self {
+        Set {
+            value,
+            hash_state: Cell::new(None),
+        }
+    }
+}
+
+impl Footprint for Set {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        usage.add_cells(
+            self.value.iter(),
+            cell_bytes::<Object>(),
+            ESTIMATED_HASH_TRIE_NODE_BYTES,
+        );
+    }
+}
+
 #[pymethods]
 impl Set {
     pub fn add(&self, py_object: PyObject) -> PyResult<Self> {
-        let new_self = Self {
-            value: self.value.insert(Object::new(py_object)),
-        };
-        Ok(new_self)
+        let object = Object::hashable(py_object)?;
+
+        let value = catch(|| self.value.insert(object.clone()))?;
+        Ok(self.derive(value, &object))
     }
 
     pub fn discard(&self, py_object: PyObject) -> PyResult<Self> {
-        let object = Object::new(py_object);
-
-        let new_self = Self {
-            value: self.value.remove(&object),
-        };
+        let object = Object::hashable(py_object)?;
 
-        Ok(new_self)
+        let value = catch(|| self.value.remove(&object))?;
+        Ok(self.derive(value, &object))
     }
 
     pub fn remove(&self, py_object: PyObject) -> PyResult<Self> {
-        let object = Object::new(py_object);
+        let object = Object::hashable(py_object)?;
 
-        if !self.value.contains(&object) {
+        if !catch(|| self.value.contains(&object))? {
             return Err(PyErr::new::<exceptions::KeyError, _>(
                 "Element is not in the set!",
             ));
         }
 
-        let new_self = Self {
-            value: self.value.remove(&object),
-        };
-
-        Ok(new_self)
+        let value = catch(|| self.value.remove(&object))?;
+        Ok(self.derive(value, &object))
     }
 
     pub fn isdisjoint(&self, other: &Set) -> PyResult<bool> {
-        Ok(self.value.is_disjoint(&other.value))
+        catch(|| self.value.is_disjoint(&other.value))
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn update(&self, py: Python, iterator: PyObject) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        let mut hash_state = self.hash_state.get();
+        for element in iterator.as_ref(py).iter()? {
+            let object = Object::hashable(element?.extract::<PyObject>()?)?;
+            let object_hash = object.py_hash()?;
+
+            let size = value.size();
+            catch(|| value.insert_mut(object))?;
+            if value.size() != size {
+                hash_state = hash_state.map(|state| frozenset_toggle(state, object_hash));
+            }
+        }
+
+        Ok(Set {
+            value,
+            hash_state: Cell::new(hash_state),
+        })
     }
 
+    #[must_use]
+    pub fn copy(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[must_use]
     #[allow(clippy::needless_pass_by_value)]
-    pub fn update(&self, iterator: PyObject) -> PyResult<Self> {
-        let gil_guard = Python::acquire_gil();
-        let py = gil_guard.python();
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
 
-        let iterator = iterator.as_ref(py).iter().unwrap();
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
 
-        let mut new_self = Self {
-            value: self.value.clone(),
-        };
-        for element in iterator {
-            let element = element.unwrap().extract::<PyObject>()?;
-            let object = Object::new(element);
-            new_self = Self {
-                value: new_self.value.insert(object),
-            };
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut value = slf.value.clone();
+        let mut copied = false;
+        for element in slf.value.iter() {
+            if let Some(object) = deepcopy_object(py, element, &memo)? {
+                catch(|| {
+                    value.remove_mut(element);
+                    value.insert_mut(object);
+                })?;
+                copied = true;
+            }
         }
-        Ok(new_self)
-    }
-}
 
-impl Hash for Set {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        // Add the hash of length so that if two collections are added one after the other it doesn't
-        // hash to the same thing as a single collection with the same elements in the same order.
-        self.value.size().hash(state);
-        for element in self.value.iter() {
-            element.hash(state);
+        if !copied {
+            return Ok(slf.into());
         }
+        Py::new(py, Self::from(value))
     }
 }
 
@@ -106,21 +186,20 @@ impl PySequenceProtocol for Set {
     }
 
     fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
-        Ok(self.value.contains(&Object::new(py_object)))
+        let object = Object::hashable(py_object)?;
+        catch(|| self.value.contains(&object))
     }
 }
 
 #[pyproto]
 impl PyIterProtocol for Set {
     fn __iter__(slf: PyRefMut<Self>) -> PyResult<crate::iterators::PyObjectIterator> {
-        let mut elements = std::vec::Vec::new();
-        for element in slf.value.iter() {
-            elements.push(extract_py_object(Some(element))?)
-        }
-
-        Ok(crate::iterators::PyObjectIterator::new(
-            elements.into_iter(),
-        ))
+        // SAFETY: `set` only feeds the iterator, whose items are owned.
+        Ok(crate::iterators::PyObjectIterator::new(unsafe {
+            crate::iterators::OwningIterator::new(slf.value.clone(), |set| {
+                set.iter().map(|element| extract_py_object(Some(element)))
+            })
+        }))
     }
 }
 
@@ -141,9 +220,12 @@ impl std::fmt::Display for Set {
     }
 }
 
-#[pyfunction(args = "*")]
-fn pset(args: &PyTuple) -> PyResult<Set> {
-    let mut set = Set::new();
+#[pyfunction(args = "*", kwargs = "**")]
+fn pset(py: Python, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<Set> {
+    let set = match keyword_only_argument("pset", "degree", kwargs)? {
+        Some(degree) => Set::from(RpdsSet::new_with_degree(checked_degree(degree)?)),
+        None => Set::new(),
+    };
     if args.is_empty() {
         return Ok(set);
     } else if args.len() > 1 {
@@ -152,23 +234,12 @@ fn pset(args: &PyTuple) -> PyResult<Set> {
         ));
     }
 
-    let iterator = args.get_item(0).as_ref().iter().unwrap();
-    for element in iterator {
-        let element = element.unwrap().extract::<PyObject>()?;
-        set = set.add(element)?;
-    }
-    Ok(set)
+    set.update(py, args.get_item(0).to_object(py))
 }
 
 #[pyfunction(args = "*")]
-fn s(args: &PyTuple) -> PyResult<Set> {
-    let mut set = Set::new();
-
-    for element in args.iter() {
-        let element = element.extract::<PyObject>()?;
-        set = set.add(element)?;
-    }
-    Ok(set)
+fn s(py: Python, args: &PyTuple) -> PyResult<Set> {
+    Set::new().update(py, args.to_object(py))
 }
 
 pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
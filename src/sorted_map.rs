@@ -0,0 +1,354 @@
+use std::cell::Cell;
+use std::ops::Bound;
+
+use pyo3::class::PyObjectProtocol;
+use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::types::PyTuple;
+use pyo3::{
+    exceptions, wrap_pyfunction, Py, PyAny, PyCell, PyErr, PyIterProtocol, PyMappingProtocol,
+    PyNativeType, PyRef, PyRefMut, PySequenceProtocol, Python, ToPyObject,
+};
+
+use crate::hashing::{frozenset_finish, frozenset_state, tuple_hash};
+use crate::iterators::{OwningIterator, PyObjectIterator, PyObjectPairIterator};
+use crate::map::for_each_item;
+use crate::memory::{Footprint, MemoryUsage};
+use crate::object::{catch, deepcopy_object, extract_py_object, Object};
+use crate::ordered::{SortKey, SortedEntries};
+
+/// A map backed by a red-black tree, which keeps its keys sorted with Python's `<`,
+/// or by the result of a key function as `sorted` does. The sort keys only order the keys:
+/// like in a `dict`, two keys are the same key if they are equal.
+#[pyclass]
+#[derive(Default)]
+pub struct SortedMap {
+    value: SortedEntries<Object>,
+    hash: Cell<Option<isize>>,
+}
+
+impl SortedMap {
+    #[must_use]
+    pub fn new(key: Option<PyObject>) -> Self {
+        SortedMap {
+            value: SortedEntries::new(key.map(Object::new)),
+            hash: Cell::new(None),
+        }
+    }
+
+    /// Hashes the map the same way as a `frozenset` of its items.
+    /// The hash is computed once, the items of a map can't change.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
+        }
+
+        let hashes = self
+            .value
+            .iter()
+            .map(|(key, value)| Ok(tuple_hash(&[key.py_hash()?, value.py_hash()?])))
+            .collect::<PyResult<Vec<_>>>()?;
+        let hash = frozenset_finish(frozenset_state(hashes), self.value.len());
+        self.hash.set(Some(hash));
+        Ok(hash)
+    }
+
+    /// Wraps entries derived from those of a map, which carry its key function along.
+    fn derive(value: SortedEntries<Object>) -> Self {
+        SortedMap {
+            value,
+            hash: Cell::new(None),
+        }
+    }
+
+    /// Wraps a key along with its sort key.
+    fn key(&self, py: Python, py_key: PyObject) -> PyResult<(SortKey, Object)> {
+        let key = Object::new(py_key);
+        Ok((self.value.sort_key(py, &key)?, key))
+    }
+
+    fn insert_item(&mut self, py: Python, key: PyObject, value: PyObject) -> PyResult<()> {
+        let (sort_key, key) = self.key(py, key)?;
+        let map = &mut self.value;
+        catch(|| map.insert_mut(sort_key, key, Object::new(value)))
+    }
+
+    /// Makes a bound of a range query, a missing key leaves the range open on that side.
+    fn bound(
+        &self,
+        py: Python,
+        key: Option<PyObject>,
+        inclusive: bool,
+    ) -> PyResult<Bound<SortKey>> {
+        Ok(match key {
+            Some(key) if inclusive => Bound::Included(self.key(py, key)?.0),
+            Some(key) => Bound::Excluded(self.key(py, key)?.0),
+            None => Bound::Unbounded,
+        })
+    }
+}
+
+impl Footprint for SortedMap {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        self.value.add_cells(usage);
+    }
+}
+
+fn item(key: &Object, value: &Object) -> PyResult<(PyObject, PyObject)> {
+    Ok((
+        extract_py_object(Some(key))?,
+        extract_py_object(Some(value))?,
+    ))
+}
+
+fn empty_error(operation: &str) -> PyErr {
+    PyErr::new::<exceptions::KeyError, _>(format!("{}(): map is empty", operation))
+}
+
+#[pymethods]
+impl SortedMap {
+    pub fn set(&self, py: Python, py_key: PyObject, py_value: PyObject) -> PyResult<Self> {
+        let (sort_key, key) = self.key(py, py_key)?;
+        let value = catch(|| self.value.insert(sort_key, key, Object::new(py_value)))?;
+        Ok(Self::derive(value))
+    }
+
+    pub fn discard(&self, py: Python, py_key: PyObject) -> PyResult<Self> {
+        let (sort_key, key) = self.key(py, py_key)?;
+        let value = catch(|| self.value.remove(&sort_key, &key))?;
+        Ok(Self::derive(value.unwrap_or_else(|| self.value.clone())))
+    }
+
+    pub fn remove(&self, py: Python, py_key: PyObject) -> PyResult<Self> {
+        let (sort_key, key) = self.key(py, py_key)?;
+        match catch(|| self.value.remove(&sort_key, &key))? {
+            Some(value) => Ok(Self::derive(value)),
+            None => Err(PyErr::new::<exceptions::KeyError, _>(extract_py_object(
+                Some(&key),
+            )?)),
+        }
+    }
+
+    pub fn get(&self, py: Python, py_key: PyObject) -> PyResult<PyObject> {
+        let (sort_key, key) = self.key(py, py_key)?;
+        match catch(|| self.value.get(&sort_key, &key))? {
+            Some((_, value)) => extract_py_object(Some(value)),
+            None => Err(PyErr::new::<exceptions::KeyError, _>(extract_py_object(
+                Some(&key),
+            )?)),
+        }
+    }
+
+    /// Returns the item with the smallest key.
+    pub fn first(&self) -> PyResult<(PyObject, PyObject)> {
+        match self.value.first() {
+            Some((key, value)) => item(key, value),
+            None => Err(empty_error("first")),
+        }
+    }
+
+    /// Returns the item with the largest key.
+    pub fn last(&self) -> PyResult<(PyObject, PyObject)> {
+        match self.value.last() {
+            Some((key, value)) => item(key, value),
+            None => Err(empty_error("last")),
+        }
+    }
+
+    /// Returns the item with the smallest key, and the map without it.
+    pub fn pop_first(&self) -> PyResult<((PyObject, PyObject), Self)> {
+        let (key, value) = self.value.first().ok_or_else(|| empty_error("pop_first"))?;
+        let rest = catch(|| self.value.without_first())?;
+        let rest = rest.unwrap_or_else(|| self.value.clone());
+        Ok((item(key, value)?, Self::derive(rest)))
+    }
+
+    /// Returns the item with the largest key less than or equal to `key`, if any.
+    pub fn floor(&self, py: Python, key: PyObject) -> PyResult<Option<(PyObject, PyObject)>> {
+        let high = self.bound(py, Some(key), true)?;
+        let floor = catch(|| self.value.range_back(&high).next())?;
+        floor.map(|(key, value)| item(key, value)).transpose()
+    }
+
+    /// Returns the item with the smallest key greater than or equal to `key`, if any.
+    pub fn ceiling(&self, py: Python, key: PyObject) -> PyResult<Option<(PyObject, PyObject)>> {
+        let low = self.bound(py, Some(key), true)?;
+        let ceiling = catch(|| self.value.range(&low, &Bound::Unbounded).next())?;
+        ceiling.map(|(key, value)| item(key, value)).transpose()
+    }
+
+    /// Iterates in order over the items whose keys lie between `low` and `high`, either of
+    /// which can be `None` to leave the range open. `inclusive` tells whether each bound is
+    /// included, by default the range is half-open like `range`.
+    #[args(low = "None", high = "None", inclusive = "(true, false)")]
+    pub fn range(
+        &self,
+        py: Python,
+        low: Option<PyObject>,
+        high: Option<PyObject>,
+        inclusive: (bool, bool),
+    ) -> PyResult<PyObjectPairIterator> {
+        let bounds = (
+            self.bound(py, low, inclusive.0)?,
+            self.bound(py, high, inclusive.1)?,
+        );
+
+        // SAFETY: `map` and `bounds` only feed the range, whose items are owned.
+        Ok(PyObjectPairIterator::new(unsafe {
+            OwningIterator::new((self.value.clone(), bounds), |(map, (low, high))| {
+                // Walking a range compares keys, hence every step may raise.
+                let mut range = map.range(low, high);
+                std::iter::from_fn(move || match catch(|| range.next()) {
+                    Ok(entry) => entry.map(|(key, value)| item(key, value)),
+                    Err(error) => Some(Err(error)),
+                })
+            })
+        }))
+    }
+
+    pub fn keys(&self) -> PyResult<PyObjectIterator> {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.iter().map(|(key, _)| extract_py_object(Some(key)))
+            })
+        }))
+    }
+
+    pub fn values(&self) -> PyResult<PyObjectIterator> {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.iter().map(|(_, value)| extract_py_object(Some(value)))
+            })
+        }))
+    }
+
+    pub fn items(&self) -> PyResult<PyObjectPairIterator> {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        Ok(PyObjectPairIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.iter().map(|(key, value)| item(key, value))
+            })
+        }))
+    }
+
+    #[args(args = "*")]
+    pub fn update(&self, py: Python, args: &PyTuple) -> PyResult<Self> {
+        let mut map = Self::derive(self.value.clone());
+        for arg in args.iter() {
+            for_each_item(py, arg, |key, value| map.insert_item(py, key, value))?;
+        }
+        Ok(map)
+    }
+
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
+
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut map = Self::derive(slf.value.empty_copy());
+        let mut copied = false;
+        for (key, value) in slf.value.iter() {
+            let key_copy = deepcopy_object(py, key, &memo)?;
+            let value_copy = deepcopy_object(py, value, &memo)?;
+            copied |= key_copy.is_some() || value_copy.is_some();
+
+            let key = key_copy.unwrap_or_else(|| key.clone());
+            let value = value_copy.unwrap_or_else(|| value.clone());
+            map.insert_item(
+                py,
+                extract_py_object(Some(&key))?,
+                extract_py_object(Some(&value))?,
+            )?;
+        }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+        Py::new(py, map)
+    }
+
+    pub fn __reversed__(&self) -> PyResult<PyObjectIterator> {
+        // SAFETY: `map` only feeds the iterator, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |map| {
+                map.iter()
+                    .rev()
+                    .map(|(key, _)| extract_py_object(Some(key)))
+            })
+        }))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for SortedMap {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.len())
+    }
+
+    fn __contains__(&self, py_key: &PyAny) -> PyResult<bool> {
+        let py = py_key.py();
+        let (sort_key, key) = self.key(py, py_key.to_object(py))?;
+        catch(|| self.value.get(&sort_key, &key).is_some())
+    }
+}
+
+#[pyproto]
+impl PyMappingProtocol for SortedMap {
+    fn __getitem__(&self, key: &PyAny) -> PyResult<PyObject> {
+        self.get(key.py(), key.to_object(key.py()))
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for SortedMap {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectIterator> {
+        slf.keys()
+    }
+}
+
+py_object_protocol!(SortedMap);
+
+impl std::fmt::Display for SortedMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "psortedmap({{")?;
+        for (index, (key, value)) in self.value.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", key, value)?;
+        }
+        write!(f, "}}")?;
+
+        if let Some(key) = self.value.key_function() {
+            write!(f, ", key={}", key)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Makes a sorted map from a mapping or from key-value pairs. The keys are sorted by the
+/// result of `key` if given, otherwise by themselves.
+#[pyfunction(initial = "None", key = "None")]
+fn psortedmap(py: Python, initial: Option<&PyAny>, key: Option<PyObject>) -> PyResult<SortedMap> {
+    let mut map = SortedMap::new(key);
+    if let Some(initial) = initial {
+        for_each_item(py, initial, |key, value| map.insert_item(py, key, value))?;
+    }
+    Ok(map)
+}
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<SortedMap>()?;
+    m.add_wrapped(wrap_pyfunction!(psortedmap)).unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,465 @@
+use std::cell::Cell;
+use std::ops::Bound;
+
+use pyo3::class::{PyNumberProtocol, PyObjectProtocol, PySequenceProtocol};
+use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
+use pyo3::{
+    exceptions, wrap_pyfunction, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol,
+    PyNativeType, PyRef, PyRefMut, Python, ToPyObject,
+};
+
+use crate::hashing::{frozenset_finish, frozenset_state};
+use crate::iterators::{OwningIterator, PyObjectIterator};
+use crate::memory::{Footprint, MemoryUsage};
+use crate::object::{catch, deepcopy_object, extract_py_object, Object};
+use crate::ordered::{SortKey, SortedEntries};
+
+type Elements = SortedEntries<()>;
+
+/// A set backed by a red-black tree, which keeps its elements sorted with Python's `<`,
+/// or by the result of a key function as `sorted` does. The sort keys only order the
+/// elements: like in a `frozenset`, two elements are the same element if they are equal.
+///
+/// rpds doesn't keep the sizes of the subtrees, so the rank of an element takes time linear
+/// in the rank rather than logarithmic.
+#[pyclass]
+#[derive(Default)]
+pub struct SortedSet {
+    value: Elements,
+    hash: Cell<Option<isize>>,
+}
+
+impl SortedSet {
+    #[must_use]
+    pub fn new(key: Option<PyObject>) -> Self {
+        SortedSet {
+            value: SortedEntries::new(key.map(Object::new)),
+            hash: Cell::new(None),
+        }
+    }
+
+    /// Hashes the set the same way as a `frozenset` of its elements.
+    /// The hash is computed once, the elements of a set can't change.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
+        }
+
+        let hashes = self
+            .value
+            .iter()
+            .map(|(element, ())| element.py_hash())
+            .collect::<PyResult<Vec<_>>>()?;
+        let hash = frozenset_finish(frozenset_state(hashes), self.value.len());
+        self.hash.set(Some(hash));
+        Ok(hash)
+    }
+
+    /// Wraps elements derived from those of a set, which carry its key function along.
+    fn derive(value: Elements) -> Self {
+        SortedSet {
+            value,
+            hash: Cell::new(None),
+        }
+    }
+
+    /// Wraps an element along with its sort key.
+    fn element(&self, py: Python, py_object: PyObject) -> PyResult<(SortKey, Object)> {
+        let element = Object::new(py_object);
+        Ok((self.value.sort_key(py, &element)?, element))
+    }
+
+    fn insert(&mut self, py: Python, py_object: PyObject) -> PyResult<()> {
+        let (sort_key, element) = self.element(py, py_object)?;
+        let set = &mut self.value;
+        catch(|| set.insert_mut(sort_key, element, ()))
+    }
+
+    /// Makes a bound of a range query, a missing element leaves the range open on that side.
+    fn bound(
+        &self,
+        py: Python,
+        py_object: Option<PyObject>,
+        inclusive: bool,
+    ) -> PyResult<Bound<SortKey>> {
+        Ok(match py_object {
+            Some(py_object) if inclusive => Bound::Included(self.element(py, py_object)?.0),
+            Some(py_object) => Bound::Excluded(self.element(py, py_object)?.0),
+            None => Bound::Unbounded,
+        })
+    }
+
+    /// The elements of `iterable` ordered like the elements of this set. Sorted sets with the
+    /// same key function are taken as they are.
+    fn elements_of(&self, py: Python, iterable: &PyAny) -> PyResult<Elements> {
+        if let Ok(other) = iterable.downcast::<PyCell<SortedSet>>() {
+            let other = other.borrow();
+            if catch(|| self.value.key_function() == other.value.key_function())? {
+                return Ok(other.value.clone());
+            }
+        }
+
+        let mut elements = Self::derive(self.value.empty_copy());
+        for element in iterable.iter()? {
+            elements.insert(py, element?.to_object(py))?;
+        }
+        Ok(elements.value)
+    }
+
+    /// The element closest to `py_object` before it, or after it if `after`. `inclusive`
+    /// tells whether an element equivalent to `py_object` counts.
+    fn neighbor(
+        &self,
+        py: Python,
+        py_object: PyObject,
+        inclusive: bool,
+        after: bool,
+    ) -> PyResult<Option<PyObject>> {
+        let bound = self.bound(py, Some(py_object), inclusive)?;
+        let neighbor = catch(|| {
+            if after {
+                self.value.range(&bound, &Bound::Unbounded).next()
+            } else {
+                self.value.range_back(&bound).next()
+            }
+        })?;
+        neighbor
+            .map(|(element, ())| extract_py_object(Some(element)))
+            .transpose()
+    }
+
+    fn union(&self, other: &Elements) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        catch(|| {
+            for (sort_key, element, ()) in other.iter_with_sort_keys() {
+                // The elements already in this set are kept, as for `frozenset`.
+                if value.get(sort_key, element).is_none() {
+                    value.insert_mut(sort_key.clone(), element.clone(), ());
+                }
+            }
+        })?;
+        Ok(Self::derive(value))
+    }
+
+    fn intersection(&self, other: &Elements) -> PyResult<Self> {
+        let mut value = self.value.empty_copy();
+        catch(|| {
+            for (sort_key, element, ()) in self.value.iter_with_sort_keys() {
+                if other.get(sort_key, element).is_some() {
+                    value.insert_mut(sort_key.clone(), element.clone(), ());
+                }
+            }
+        })?;
+        Ok(Self::derive(value))
+    }
+
+    fn difference(&self, other: &Elements) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        catch(|| {
+            for (sort_key, element, ()) in other.iter_with_sort_keys() {
+                if let Some(rest) = value.remove(sort_key, element) {
+                    value = rest;
+                }
+            }
+        })?;
+        Ok(Self::derive(value))
+    }
+
+    fn symmetric_difference(&self, other: &Elements) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        catch(|| {
+            for (sort_key, element, ()) in other.iter_with_sort_keys() {
+                if let Some(rest) = value.remove(sort_key, element) {
+                    value = rest;
+                } else {
+                    value.insert_mut(sort_key.clone(), element.clone(), ());
+                }
+            }
+        })?;
+        Ok(Self::derive(value))
+    }
+}
+
+impl Footprint for SortedSet {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        self.value.add_cells(usage);
+    }
+}
+
+fn empty_error(operation: &str) -> PyErr {
+    PyErr::new::<exceptions::KeyError, _>(format!("{}(): set is empty", operation))
+}
+
+#[pymethods]
+impl SortedSet {
+    pub fn add(&self, py: Python, py_object: PyObject) -> PyResult<Self> {
+        let (sort_key, element) = self.element(py, py_object)?;
+        let value = catch(|| self.value.insert(sort_key, element, ()))?;
+        Ok(Self::derive(value))
+    }
+
+    pub fn discard(&self, py: Python, py_object: PyObject) -> PyResult<Self> {
+        let (sort_key, element) = self.element(py, py_object)?;
+        let value = catch(|| self.value.remove(&sort_key, &element))?;
+        Ok(Self::derive(value.unwrap_or_else(|| self.value.clone())))
+    }
+
+    pub fn remove(&self, py: Python, py_object: PyObject) -> PyResult<Self> {
+        let (sort_key, element) = self.element(py, py_object)?;
+        match catch(|| self.value.remove(&sort_key, &element))? {
+            Some(value) => Ok(Self::derive(value)),
+            None => Err(PyErr::new::<exceptions::KeyError, _>(extract_py_object(
+                Some(&element),
+            )?)),
+        }
+    }
+
+    pub fn update(&self, py: Python, iterable: &PyAny) -> PyResult<Self> {
+        let mut set = Self::derive(self.value.clone());
+        for element in iterable.iter()? {
+            set.insert(py, element?.to_object(py))?;
+        }
+        Ok(set)
+    }
+
+    /// Returns the smallest element.
+    pub fn first(&self) -> PyResult<PyObject> {
+        match self.value.first() {
+            Some((element, ())) => extract_py_object(Some(element)),
+            None => Err(empty_error("first")),
+        }
+    }
+
+    /// Returns the largest element.
+    pub fn last(&self) -> PyResult<PyObject> {
+        match self.value.last() {
+            Some((element, ())) => extract_py_object(Some(element)),
+            None => Err(empty_error("last")),
+        }
+    }
+
+    /// Returns the largest element less than or equal to `py_object`, if any.
+    pub fn floor(&self, py: Python, py_object: PyObject) -> PyResult<Option<PyObject>> {
+        self.neighbor(py, py_object, true, false)
+    }
+
+    /// Returns the smallest element greater than or equal to `py_object`, if any.
+    pub fn ceiling(&self, py: Python, py_object: PyObject) -> PyResult<Option<PyObject>> {
+        self.neighbor(py, py_object, true, true)
+    }
+
+    /// Returns the largest element strictly less than `py_object`, if any.
+    pub fn lower(&self, py: Python, py_object: PyObject) -> PyResult<Option<PyObject>> {
+        self.neighbor(py, py_object, false, false)
+    }
+
+    /// Returns the smallest element strictly greater than `py_object`, if any.
+    pub fn higher(&self, py: Python, py_object: PyObject) -> PyResult<Option<PyObject>> {
+        self.neighbor(py, py_object, false, true)
+    }
+
+    /// Returns the number of elements less than `py_object`, whether it is in the set or not.
+    /// This walks the elements before `py_object`, so it takes O(rank) time, not O(log n).
+    pub fn rank(&self, py: Python, py_object: PyObject) -> PyResult<usize> {
+        let (sort_key, _) = self.element(py, py_object)?;
+        catch(|| self.value.rank(&sort_key))
+    }
+
+    /// Returns the position of `py_object` in the sorted elements.
+    /// This walks the elements before `py_object`, so it takes O(rank) time, not O(log n).
+    pub fn index_of(&self, py: Python, py_object: PyObject) -> PyResult<usize> {
+        let (sort_key, element) = self.element(py, py_object)?;
+        catch(|| self.value.position(&sort_key, &element))?
+            .ok_or_else(|| PyErr::new::<exceptions::ValueError, _>("Element not in set!"))
+    }
+
+    /// Iterates in order over the elements which lie between `low` and `high`, either of
+    /// which can be `None` to leave the range open. `inclusive` tells whether each bound is
+    /// included, by default the range is half-open like `range`.
+    #[args(low = "None", high = "None", inclusive = "(true, false)")]
+    pub fn range(
+        &self,
+        py: Python,
+        low: Option<PyObject>,
+        high: Option<PyObject>,
+        inclusive: (bool, bool),
+    ) -> PyResult<PyObjectIterator> {
+        let bounds = (
+            self.bound(py, low, inclusive.0)?,
+            self.bound(py, high, inclusive.1)?,
+        );
+
+        // SAFETY: `set` and `bounds` only feed the range, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new((self.value.clone(), bounds), |(set, (low, high))| {
+                // Walking a range compares elements, hence every step may raise.
+                let mut range = set.range(low, high);
+                std::iter::from_fn(move || match catch(|| range.next()) {
+                    Ok(element) => element.map(|(element, ())| extract_py_object(Some(element))),
+                    Err(error) => Some(Err(error)),
+                })
+            })
+        }))
+    }
+
+    #[name = "union"]
+    pub fn py_union(&self, py: Python, iterable: &PyAny) -> PyResult<Self> {
+        self.union(&self.elements_of(py, iterable)?)
+    }
+
+    #[name = "intersection"]
+    pub fn py_intersection(&self, py: Python, iterable: &PyAny) -> PyResult<Self> {
+        self.intersection(&self.elements_of(py, iterable)?)
+    }
+
+    #[name = "difference"]
+    pub fn py_difference(&self, py: Python, iterable: &PyAny) -> PyResult<Self> {
+        self.difference(&self.elements_of(py, iterable)?)
+    }
+
+    #[name = "symmetric_difference"]
+    pub fn py_symmetric_difference(&self, py: Python, iterable: &PyAny) -> PyResult<Self> {
+        self.symmetric_difference(&self.elements_of(py, iterable)?)
+    }
+
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
+
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut set = Self::derive(slf.value.empty_copy());
+        let mut copied = false;
+        for (element, ()) in slf.value.iter() {
+            let element = match deepcopy_object(py, element, &memo)? {
+                Some(copy) => {
+                    copied = true;
+                    copy
+                }
+                None => element.clone(),
+            };
+            set.insert(py, extract_py_object(Some(&element))?)?;
+        }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+        Py::new(py, set)
+    }
+
+    pub fn __reversed__(&self) -> PyResult<PyObjectIterator> {
+        // SAFETY: `set` only feeds the iterator, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new(self.value.clone(), |set| {
+                set.iter()
+                    .rev()
+                    .map(|(element, ())| extract_py_object(Some(element)))
+            })
+        }))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for SortedSet {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.value.len())
+    }
+
+    fn __contains__(&self, py_object: &PyAny) -> PyResult<bool> {
+        let py = py_object.py();
+        let (sort_key, element) = self.element(py, py_object.to_object(py))?;
+        catch(|| self.value.get(&sort_key, &element).is_some())
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for SortedSet {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<PyObjectIterator> {
+        // SAFETY: `set` only feeds the iterator, whose items are owned.
+        Ok(PyObjectIterator::new(unsafe {
+            OwningIterator::new(slf.value.clone(), |set| {
+                set.iter()
+                    .map(|(element, ())| extract_py_object(Some(element)))
+            })
+        }))
+    }
+}
+
+/// Applies a set operation to two sorted sets, like `frozenset` operators do for two sets.
+fn operator<F>(lhs: &PyAny, rhs: &PyAny, operation: F) -> PyResult<PyObject>
+where
+    F: FnOnce(&SortedSet, &Elements) -> PyResult<SortedSet>,
+{
+    let py = lhs.py();
+    let lhs = match lhs.downcast::<PyCell<SortedSet>>() {
+        Ok(lhs) if rhs.downcast::<PyCell<SortedSet>>().is_ok() => lhs.borrow(),
+        _ => return Ok(py.NotImplemented()),
+    };
+
+    let result = operation(&lhs, &lhs.elements_of(py, rhs)?)?;
+    Ok(Py::new(py, result)?.to_object(py))
+}
+
+#[pyproto]
+impl PyNumberProtocol for SortedSet {
+    fn __or__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        operator(lhs, rhs, SortedSet::union)
+    }
+
+    fn __and__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        operator(lhs, rhs, SortedSet::intersection)
+    }
+
+    fn __sub__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        operator(lhs, rhs, SortedSet::difference)
+    }
+
+    fn __xor__(lhs: &PyAny, rhs: &PyAny) -> PyResult<PyObject> {
+        operator(lhs, rhs, SortedSet::symmetric_difference)
+    }
+}
+
+py_object_protocol!(SortedSet);
+
+impl std::fmt::Display for SortedSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "psortedset([")?;
+        for (index, (element, ())) in self.value.iter().enumerate() {
+            if index != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", element)?;
+        }
+        write!(f, "]")?;
+
+        if let Some(key) = self.value.key_function() {
+            write!(f, ", key={}", key)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Makes a sorted set from the elements of an iterable. The elements are sorted by the
+/// result of `key` if given, otherwise by themselves.
+#[pyfunction(iterable = "None", key = "None")]
+fn psortedset(py: Python, iterable: Option<&PyAny>, key: Option<PyObject>) -> PyResult<SortedSet> {
+    let set = SortedSet::new(key);
+    match iterable {
+        Some(iterable) => set.update(py, iterable),
+        None => Ok(set),
+    }
+}
+
+pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<SortedSet>()?;
+    m.add_wrapped(wrap_pyfunction!(psortedset)).unwrap();
+
+    Ok(())
+}
@@ -1,16 +1,19 @@
-use std::borrow::Borrow;
+use std::cell::Cell;
 use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
 
+use archery::SharedPointerKind;
 use pyo3::class::{PyObjectProtocol, PySequenceProtocol};
 use pyo3::prelude::{pyclass, pyfunction, pymethods, pyproto, PyModule, PyObject, PyResult};
-use pyo3::types::PyTuple;
+use pyo3::types::{PyDict, PyTuple};
 use pyo3::{
-    exceptions, wrap_pyfunction, AsPyRef, ObjectProtocol, PyAny, PyCell, PyErr, PyIterProtocol,
-    PyRefMut, Python,
+    exceptions, wrap_pyfunction, AsPyRef, ObjectProtocol, Py, PyAny, PyCell, PyErr, PyIterProtocol,
+    PyRef, PyRefMut, Python,
 };
 
-use crate::object::{extract_py_object, Object};
+use crate::hashing::ordered_hash;
+use crate::memory::{cell_bytes, Footprint, MemoryUsage, ESTIMATED_VECTOR_SLOT_BYTES};
+use crate::object::{catch, deepcopy_object, extract_py_object, keyword_only_argument, Object};
 
 type RpdsVector = rpds::Vector<Object>;
 
@@ -18,42 +21,117 @@ type RpdsVector = rpds::Vector<Object>;
 #[derive(Default)]
 pub struct Vector {
     value: RpdsVector,
+    hash: Cell<Option<isize>>,
 }
 
 impl Vector {
     #[must_use]
     pub fn new() -> Self {
-        Vector {
-            value: RpdsVector::new(),
-        }
+        Self::from(RpdsVector::new())
     }
 
-    fn normalize_index(&self, index: isize) -> PyResult<usize> {
-        if index == 0 {
-            return Ok(0);
+    /// The hash is computed once, the elements of a vector can't change.
+    pub fn py_hash(&self) -> PyResult<isize> {
+        if let Some(hash) = self.hash.get() {
+            return Ok(hash);
         }
 
-        let length = isize::try_from(self.value.len())?;
+        let hash = ordered_hash(self)?;
+        self.hash.set(Some(hash));
+        Ok(hash)
+    }
 
-        let mut index = index;
-        if index < 0 {
-            index += length;
-        }
+    fn without(&self, start: usize, stop: usize) -> Self {
+        Self::from(without_range(&self.value, start, stop))
+    }
+}
 
-        if index < 0 {
-            return Err(PyErr::new::<exceptions::IndexError, _>(format!(
-                "Index out of range: {}",
-                index
-            )));
+pub fn normalize_index(length: usize, index: isize) -> PyResult<usize> {
+    if index == 0 {
+        return Ok(0);
+    }
+
+    let length = isize::try_from(length)?;
+
+    let mut index = index;
+    if index < 0 {
+        index += length;
+    }
+
+    if index < 0 {
+        return Err(PyErr::new::<exceptions::IndexError, _>(format!(
+            "Index out of range: {}",
+            index
+        )));
+    }
+    Ok(usize::try_from(index)?)
+}
+
+/// Normalizes `start` and `stop` the way slices do: negative bounds count from the end
+/// and out of range bounds are clamped to the vector.
+pub fn normalize_range(
+    length: usize,
+    start: isize,
+    stop: Option<isize>,
+) -> PyResult<(usize, usize)> {
+    let length = isize::try_from(length)?;
+
+    let clamp = |index: isize| {
+        let index = if index < 0 { index + length } else { index };
+        index.max(0).min(length)
+    };
+    let start = clamp(start);
+    let stop = clamp(stop.unwrap_or(length));
+
+    Ok((usize::try_from(start)?, usize::try_from(stop)?))
+}
+
+/// Removes the elements from `start` to `stop` by shifting the ones after them to the front
+/// and dropping the last slots. Only the suffix is copied, the prefix stays shared.
+#[must_use]
+pub fn without_range<T: Clone, P: SharedPointerKind>(
+    vector: &rpds::Vector<T, P>,
+    start: usize,
+    stop: usize,
+) -> rpds::Vector<T, P> {
+    let removed = stop - start;
+    let mut value = vector.clone();
+    if removed == 0 {
+        return value;
+    }
+
+    for index in start..vector.len() - removed {
+        value.set_mut(index, vector[index + removed].clone());
+    }
+    for _ in 0..removed {
+        value.drop_last_mut();
+    }
+    value
+}
+
+impl From<RpdsVector> for Vector {
+    fn from(value: RpdsVector) -> Self {
+        Vector {
+            value,
+            hash: Cell::new(None),
         }
-        Ok(usize::try_from(index)?)
+    }
+}
+
+impl Footprint for Vector {
+    fn add_cells(&self, usage: &mut MemoryUsage) {
+        usage.add_cells(
+            self.value.iter(),
+            cell_bytes::<Object>(),
+            ESTIMATED_VECTOR_SLOT_BYTES,
+        );
     }
 }
 
 #[pymethods]
 impl Vector {
     pub fn set(&self, index: isize, py_object: PyObject) -> PyResult<Self> {
-        let index = self.normalize_index(index)?;
+        let index = normalize_index(self.value.len(), index)?;
 
         let object = Object::new(py_object);
         let new_value = if index == self.value.len() {
@@ -63,7 +141,7 @@ impl Vector {
         };
 
         match new_value {
-            Some(value) => Ok(Self { value }),
+            Some(value) => Ok(Self::from(value)),
             None => Err(PyErr::new::<exceptions::IndexError, _>(format!(
                 "Index out of range: {}",
                 index
@@ -72,34 +150,19 @@ impl Vector {
     }
 
     pub fn append(&self, py_object: PyObject) -> PyResult<Self> {
-        let new_self = Self {
-            value: self.value.push_back(Object::new(py_object)),
-        };
+        let new_self = Self::from(self.value.push_back(Object::new(py_object)));
         Ok(new_self)
     }
 
     #[allow(clippy::needless_pass_by_value)]
-    pub fn extend(&self, iterator: PyObject) -> PyResult<Self> {
-        let gil_guard = Python::acquire_gil();
-        let py = gil_guard.python();
-
-        let iterator = iterator.as_ref(py).iter().unwrap();
-
-        let mut new_self = Self {
-            value: self.value.clone(),
-        };
-        for element in iterator {
-            let element = element.unwrap().extract::<PyObject>()?;
-            let object = Object::new(element);
-            new_self = Self {
-                value: new_self.value.push_back(object),
-            };
-        }
-        Ok(new_self)
+    pub fn extend(&self, py: Python, iterator: PyObject) -> PyResult<Self> {
+        let mut value = self.value.clone();
+        push_back_all(&mut value, iterator.as_ref(py))?;
+        Ok(Self::from(value))
     }
 
     pub fn get(&self, index: isize) -> PyResult<PyObject> {
-        let index = self.normalize_index(index)?;
+        let index = normalize_index(self.value.len(), index)?;
 
         if index >= self.value.len() {
             return Err(PyErr::new::<exceptions::IndexError, _>(format!(
@@ -111,63 +174,72 @@ impl Vector {
         extract_py_object(self.value.get(index))
     }
 
-    pub fn index(&self, py_object: PyObject) -> PyResult<usize> {
+    #[args(start = "0", stop = "None")]
+    pub fn index(&self, py_object: PyObject, start: isize, stop: Option<isize>) -> PyResult<usize> {
         let object = Object::new(py_object);
-
-        for (index, element) in self.value.iter().enumerate() {
-            let element = element.clone();
-            if object == element {
-                return Ok(index);
-            }
+        let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+
+        let position = catch(|| {
+            self.value
+                .iter()
+                .skip(start)
+                .take(stop.saturating_sub(start))
+                .position(|element| *element == object)
+        })?;
+
+        if let Some(position) = position {
+            return Ok(start + position);
         }
-
         Err(PyErr::new::<exceptions::ValueError, _>(
             "Element not in vector!",
         ))
     }
 
-    pub fn count(&self, py_object: PyObject) -> PyResult<usize> {
+    #[args(start = "0", stop = "None")]
+    pub fn count(&self, py_object: PyObject, start: isize, stop: Option<isize>) -> PyResult<usize> {
         let object = Object::new(py_object);
-
-        let mut count = 0;
-        for element in self.value.iter() {
-            let element = element.clone();
-            if object == element {
-                count += 1;
-            }
-        }
-
-        Ok(count)
+        let (start, stop) = normalize_range(self.value.len(), start, stop)?;
+
+        catch(|| {
+            self.value
+                .iter()
+                .skip(start)
+                .take(stop.saturating_sub(start))
+                .filter(|element| **element == object)
+                .count()
+        })
     }
 
     pub fn remove(&self, py_object: PyObject) -> PyResult<Self> {
         let object = Object::new(py_object);
 
-        let mut vector = Vector::new();
-        let mut removed_once = false;
-        for element in self.value.iter() {
-            let element = element.clone();
-            if object != element || removed_once {
-                let element = extract_py_object(Some(element.borrow()))?;
-                vector = vector.append(element)?;
-            } else {
-                removed_once = true;
-            }
+        let position = catch(|| self.value.iter().position(|element| *element == object))?;
+        let position = position
+            .ok_or_else(|| PyErr::new::<exceptions::ValueError, _>("Element not in vector!"))?;
+
+        Ok(self.without(position, position + 1))
+    }
+
+    #[args(stop = "None")]
+    pub fn delete(&self, index: isize, stop: Option<isize>) -> PyResult<Self> {
+        if let Some(stop) = stop {
+            let (start, stop) = normalize_range(self.value.len(), index, Some(stop))?;
+            return Ok(self.without(start, stop.max(start)));
         }
 
-        if vector.value.len() == self.value.len() {
-            return Err(PyErr::new::<exceptions::ValueError, _>(
-                "Element not in vector!",
-            ));
+        let index = normalize_index(self.value.len(), index)?;
+        if index >= self.value.len() {
+            return Err(PyErr::new::<exceptions::IndexError, _>(format!(
+                "Index out of range: {}",
+                index
+            )));
         }
-        Ok(vector)
+        Ok(self.without(index, index + 1))
     }
 
     #[args(args = "*")]
     pub fn mset(&self, args: &PyTuple) -> PyResult<Vector> {
-        let mut vector = Self {
-            value: self.value.clone(),
-        };
+        let mut vector = Self::from(self.value.clone());
 
         let mut arg_index = 0;
         loop {
@@ -187,6 +259,40 @@ impl Vector {
         }
         Ok(vector)
     }
+
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __sizeof__(slf: PyRef<Self>) -> usize {
+        Self::size_of(&slf)
+    }
+
+    #[must_use]
+    pub fn __copy__(slf: PyRef<Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn __deepcopy__(slf: PyRef<Self>, py: Python, memo: PyObject) -> PyResult<Py<Self>> {
+        let mut value = slf.value.clone();
+        let mut copied = false;
+        for (index, element) in slf.value.iter().enumerate() {
+            if let Some(object) = deepcopy_object(py, element, &memo)? {
+                value.set_mut(index, object);
+                copied = true;
+            }
+        }
+
+        if !copied {
+            return Ok(slf.into());
+        }
+        Py::new(py, Self::from(value))
+    }
+
+    pub fn __reversed__(&self) -> PyResult<crate::iterators::PyObjectIterator> {
+        Ok(crate::iterators::PyObjectIterator::new(
+            crate::iterators::VectorCursor::new(self.value.clone()).rev(),
+        ))
+    }
 }
 
 impl Hash for Vector {
@@ -221,18 +327,18 @@ impl PySequenceProtocol for Vector {
         }
         self.get(index)
     }
+
+    fn __contains__(&self, py_object: PyObject) -> PyResult<bool> {
+        let object = Object::new(py_object);
+        catch(|| self.value.iter().any(|element| *element == object))
+    }
 }
 
 #[pyproto]
 impl PyIterProtocol for Vector {
     fn __iter__(slf: PyRefMut<Self>) -> PyResult<crate::iterators::PyObjectIterator> {
-        let mut elements = std::vec::Vec::new();
-        for element in slf.value.iter() {
-            elements.push(extract_py_object(Some(element))?)
-        }
-
         Ok(crate::iterators::PyObjectIterator::new(
-            elements.into_iter(),
+            crate::iterators::VectorCursor::new(slf.value.clone()),
         ))
     }
 }
@@ -254,34 +360,51 @@ impl std::fmt::Display for Vector {
     }
 }
 
-#[pyfunction(args = "*")]
-fn pvector(args: &PyTuple) -> PyResult<Vector> {
-    let mut vector = Vector::new();
+/// Appends the elements of an iterable in place. The path to the tail is only copied while
+/// it's still shared, so building a vector this way doesn't allocate a wrapper per element.
+fn push_back_all(value: &mut RpdsVector, iterable: &PyAny) -> PyResult<()> {
+    for element in iterable.iter()? {
+        value.push_back_mut(Object::new(element?.extract::<PyObject>()?));
+    }
+    Ok(())
+}
+
+/// Turns the number of children of the trie nodes into the number of index bits used per level.
+fn checked_bits(branching_factor: usize) -> PyResult<u8> {
+    if !branching_factor.is_power_of_two() || !(2..=256).contains(&branching_factor) {
+        return Err(PyErr::new::<exceptions::ValueError, _>(format!(
+            "branching_factor must be a power of two between 2 and 256, got {}",
+            branching_factor
+        )));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(branching_factor.trailing_zeros() as u8)
+}
+
+#[pyfunction(args = "*", kwargs = "**")]
+fn pvector(args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<Vector> {
+    let mut value = match keyword_only_argument("pvector", "branching_factor", kwargs)? {
+        Some(branching_factor) => RpdsVector::new_with_bits(checked_bits(branching_factor)?),
+        None => RpdsVector::new(),
+    };
     if args.is_empty() {
-        return Ok(vector);
+        return Ok(Vector::from(value));
     } else if args.len() > 1 {
         return Err(PyErr::new::<exceptions::ValueError, _>(
             "Incorrect number of arguments!!",
         ));
     }
 
-    let iterator = args.get_item(0).as_ref().iter().unwrap();
-    for element in iterator {
-        let element = element.unwrap().extract::<PyObject>()?;
-        vector = vector.append(element)?;
-    }
-    Ok(vector)
+    push_back_all(&mut value, args.get_item(0))?;
+    Ok(Vector::from(value))
 }
 
 #[pyfunction(args = "*")]
 fn v(args: &PyTuple) -> PyResult<Vector> {
-    let mut vector = Vector::new();
-
-    for element in args.iter() {
-        let element = element.extract::<PyObject>()?;
-        vector = vector.append(element)?;
-    }
-    Ok(vector)
+    let mut value = RpdsVector::new();
+    push_back_all(&mut value, args.as_ref())?;
+    Ok(Vector::from(value))
 }
 
 pub fn py_binding(_py: Python, m: &PyModule) -> PyResult<()> {
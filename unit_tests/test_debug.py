@@ -0,0 +1,54 @@
+import pytest
+
+import pyrpds.debug
+from pyrpds import pvector, plist, pmap, pset, psortedmap, pvector_i64, pvector_rrb
+from pyrpds.debug import depth, node_count, shares_structure, shared_fraction
+
+
+def test_derived_containers_share_structure():
+    vector = pvector_rrb(range(1000))
+    assert shares_structure(vector, vector.append(1000))
+    assert shares_structure(vector, vector.set(0, -1))
+    assert shares_structure(vector, vector + vector)
+    assert not shares_structure(vector, pvector_rrb(range(1000)))
+
+    assert not shares_structure(pvector_rrb(), pvector_rrb())
+
+
+def test_shared_fraction_after_update():
+    vector = pvector_rrb(range(1000))
+
+    assert shared_fraction(vector, vector) == 1.0
+    # Only the root and the first leaf are copied.
+    assert shared_fraction(vector, vector.set(0, -1)) == 31 / 33
+    assert shared_fraction(vector, pvector_rrb(range(1000))) == 0.0
+    assert shared_fraction(pvector_rrb(), vector) == 0.0
+
+
+def test_rrb_vector_nodes_are_counted_once():
+    assert (node_count(pvector_rrb()), depth(pvector_rrb())) == (1, 1)
+
+    vector = pvector_rrb(range(1000))
+    assert (node_count(vector), depth(vector)) == (33, 2)
+    assert node_count(vector + vector) < 2 * node_count(vector)
+
+
+def test_rpds_nodes_are_private():
+    for container in (pvector(), plist(), pmap(), pset(), pvector_i64()):
+        for function in (node_count, depth):
+            with pytest.raises(NotImplementedError):
+                function(container)
+        for function in (shares_structure, shared_fraction):
+            with pytest.raises(NotImplementedError):
+                function(container, container)
+
+
+def test_module_is_importable():
+    assert pyrpds.debug.shares_structure is shares_structure
+
+    with pytest.raises(TypeError):
+        shares_structure(pvector_rrb(), [])
+    with pytest.raises(TypeError):
+        node_count(psortedmap())
+    with pytest.raises(TypeError):
+        depth([])
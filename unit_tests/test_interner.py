@@ -0,0 +1,85 @@
+import pytest
+
+from pyrpds import Interner, intern, pmap, pset, pvector, pvector_rrb
+
+
+def test_equal_containers_are_interned_to_one_instance():
+    interner = Interner()
+    vector = interner.intern(pvector(range(100)))
+    assert interner.intern(pvector(range(100))) is vector
+    assert interner.intern(vector) is vector
+    assert interner.intern(pvector(range(99))) is not vector
+
+    assert len(interner) == 2
+    assert vector in interner
+    assert pvector(range(100)) in interner
+    assert pvector() not in interner
+    with pytest.raises(TypeError):
+        pvector([[]]) in interner
+
+
+def test_nested_containers_are_interned():
+    interner = Interner()
+    shared = interner.intern(pvector([1, 2, 3]))
+
+    map_0 = interner.intern(pmap({"a": pvector([1, 2, 3]), "b": pvector([4])}))
+    assert map_0["a"] is shared
+
+    set_0 = interner.intern(pset([pvector([1, 2, 3]), 1]))
+    assert any(element is shared for element in set_0)
+
+    rrb = interner.intern(pvector_rrb([pvector([4]), 5]))
+    assert rrb[0] is map_0["b"]
+
+    keys = interner.intern(pmap({pvector([1, 2, 3]): 1}))
+    assert next(iter(keys.keys())) is shared
+
+
+def test_interned_values_compare_equal():
+    interner = Interner()
+    map_0 = interner.intern(pmap({i: pvector([i]) for i in range(10)}))
+    assert map_0 == interner.intern(pmap({i: pvector([i]) for i in range(10)}))
+    assert not map_0 != map_0
+
+
+def test_statistics():
+    interner = Interner()
+    interner.intern(pmap({i: i for i in range(100)}))
+    assert interner.hits == 0
+    assert interner.misses == 1
+    assert interner.saved_bytes == 0
+
+    duplicate = pmap({i: i for i in range(100)})
+    interner.intern(duplicate)
+    assert interner.hits == 1
+    assert interner.saved_bytes == duplicate.__sizeof__()
+
+
+def test_other_objects_are_returned_unchanged():
+    interner = Interner()
+    obj = object()
+    assert interner.intern(obj) is obj
+    assert len(interner) == 0
+
+
+def test_unhashable_elements_raise_type_error():
+    interner = Interner()
+    with pytest.raises(TypeError):
+        interner.intern(pvector([[]]))
+
+
+def test_clear():
+    interner = Interner()
+    vector = interner.intern(pvector([1]))
+    interner.intern(pvector([1]))
+    assert (interner.hits, interner.misses) == (1, 1)
+
+    interner.clear()
+    assert len(interner) == 0
+    assert (interner.hits, interner.misses, interner.saved_bytes) == (0, 0, 0)
+    assert interner.intern(pvector([1])) is not vector
+
+
+def test_module_interner():
+    vector = intern(pvector(["module"]))
+    assert intern(pvector(["module"])) is vector
@@ -1,3 +1,5 @@
+import pytest
+
 from pyrpds import plist, l
 
 
@@ -67,3 +69,44 @@ def test_iter():
         container.push_front(element)
     for index, element in enumerate(container):
         assert index == element
+
+
+def test_iter_is_lazy():
+    container = l(2, 1, 0)
+    iterator = iter(container)
+    del container
+
+    assert next(iterator) == 0
+    assert list(iterator) == [1, 2]
+
+
+def test_reversed():
+    assert list(reversed(l())) == []
+    assert list(reversed(l(2, 1, 0))) == [2, 1, 0]
+
+
+def test_refuses_to_hash_when_members_are_unhashable():
+    container = l(1, [2])
+    assert container == l(1, [2])
+
+    with pytest.raises(TypeError):
+        hash(container)
+
+
+def test_copy_returns_reference_to_self():
+    import copy
+
+    list_0 = l(1, "a")
+    assert copy.copy(list_0) is list_0
+    assert copy.deepcopy(list_0) is list_0
+
+
+def test_deepcopy_copies_mutable_elements():
+    import copy
+
+    list_0 = l(1, [2])
+    list_1 = copy.deepcopy(list_0)
+
+    assert list_1 == list_0
+    assert list_1.first is not list_0.first
+    assert len(list_1) == 2
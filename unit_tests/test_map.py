@@ -1,13 +1,13 @@
 import pytest
 import pickle
 
-from pyrpds import pmap, m, Vector as PVector
+from pyrpds import pmap, m, PMapKeysView, PMapValuesView, PMapItemsView
 
 
 def test_map():
     map_0 = pmap()
     assert len(map_0) == 0
-    assert hash(map_0) == -4800647303603446203
+    assert hash(map_0) == hash(frozenset())
     assert set(map_0.values()) == set()
 
     map_1 = map_0.set(0, 0)
@@ -15,7 +15,7 @@ def test_map():
     assert map_1.get(0) == 0
     assert map_1[0] == 0
     assert len(map_1) == 1
-    assert hash(map_1) == 2885792717948792520
+    assert hash(map_1) == hash(frozenset({(0, 0)}))
     assert set(map_1.values()) == {0}
 
     map_2 = map_1.set("1", 1)
@@ -84,6 +84,163 @@ def test_iter():
         assert index == key and index == container[key]
 
 
+def test_lazy_iterations():
+    container = pmap({key: str(key) for key in range(100)})
+    keys = container.iterkeys()
+    values = container.itervalues()
+    items = container.iteritems()
+    del container
+
+    assert sorted(keys) == list(range(100))
+    assert sorted(values) == sorted(str(key) for key in range(100))
+    assert sorted(items) == [(key, str(key)) for key in range(100)]
+
+
+def test_views():
+    container = m(a=1, b=2)
+    keys = container.keys()
+    values = container.values()
+    items = container.items()
+
+    assert len(keys) == len(values) == len(items) == 2
+    assert "a" in keys and "c" not in keys
+    assert 2 in values and 3 not in values
+    assert ("a", 1) in items and ("a", 2) not in items and "a" not in items
+
+    assert keys == {"a", "b"} and keys != ["a", "b"]
+    assert keys <= {"a", "b", "c"}
+    assert items == {("a", 1), ("b", 2)}
+    assert keys == m(b=3, a=4).keys()
+
+    assert keys & {"a", "c"} == {"a"}
+    assert keys | ["c"] == {"a", "b", "c"}
+    assert keys - {"a"} == {"b"}
+    assert {"a", "c"} - keys == {"c"}
+    assert keys ^ {"a", "c"} == {"b", "c"}
+    assert items & {("b", 2), ("b", 3)} == {("b", 2)}
+    assert keys.isdisjoint({"c"}) and not items.isdisjoint([("a", 1)])
+
+    assert repr(m(a=1).keys()) == "PMapKeysView(['a'])"
+    assert repr(m(a=1).values()) == "PMapValuesView([1])"
+    assert repr(m(a=1).items()) == "PMapItemsView([('a', 1)])"
+
+
+def test_refuses_to_hash_when_values_are_unhashable():
+    container = m(a=[1])
+    assert container["a"] == [1]
+    assert container == m(a=[1])
+
+    with pytest.raises(TypeError):
+        hash(container)
+    with pytest.raises(TypeError):
+        pmap({container: 1})
+
+
+def test_failing_repr():
+    class A:
+        def __repr__(self):
+            raise ValueError("oh no!")
+
+    with pytest.raises(ValueError):
+        repr(m(a=A()))
+    with pytest.raises(ValueError):
+        repr(pmap({"a": A()}).values())
+
+
+def test_failing_eq_and_hash_raise_original_error():
+    class Unequal:
+        def __hash__(self):
+            return hash("a")
+
+        def __eq__(self, other):
+            raise ValueError("cannot compare")
+
+    key = Unequal()
+    map_0 = pmap({key: 1})
+
+    with pytest.raises(TypeError):
+        map_0.set([], 2)
+    with pytest.raises(ValueError):
+        map_0.set("a", 2)
+    with pytest.raises(ValueError):
+        map_0["a"]
+
+    assert list(map_0.items()) == [(key, 1)]
+
+
+def test_hash_matches_frozenset_of_items():
+    contents = {"a": 1, 2: "b", (3, 4): None}
+
+    assert hash(pmap(contents)) == hash(frozenset(contents.items()))
+    assert hash(pmap(contents)) == hash(pmap(dict(reversed(list(contents.items())))))
+
+
+def test_derived_maps_update_the_cached_hash():
+    map_0 = pmap({"a": 1, "b": 2})
+    hash(map_0)
+
+    assert hash(map_0.set("c", 3)) == hash(pmap({"a": 1, "b": 2, "c": 3}))
+    assert hash(map_0.set("a", 3)) == hash(pmap({"a": 3, "b": 2}))
+    assert hash(map_0.remove("a")) == hash(pmap({"b": 2}))
+    assert hash(map_0.discard("c")) == hash(map_0)
+    assert hash(map_0.update({"b": 4, "d": 5})) == hash(pmap({"a": 1, "b": 4, "d": 5}))
+    assert hash(map_0.update({"a": 5}, {"a": 6, "e": 7})) == hash(pmap({"a": 6, "b": 2, "e": 7}))
+
+    with pytest.raises(TypeError):
+        hash(map_0.set("c", []))
+    assert hash(map_0.set("c", []).set("c", 3)) == hash(pmap({"a": 1, "b": 2, "c": 3}))
+
+
+def test_key_is_hashed_once():
+    class CountingHash:
+        calls = 0
+
+        def __hash__(self):
+            CountingHash.calls += 1
+            return 1
+
+    key = CountingHash()
+    map_0 = pmap().set(key, 1)
+    assert CountingHash.calls == 1
+
+    map_1 = map_0.set("a", 2).set("b", 3).remove("a")
+    assert hash(map_1) == hash(map_1.set("c", 4).discard("c"))
+    assert CountingHash.calls == 1
+
+    # A lookup wraps the key it is given anew, hence hashes it again.
+    assert map_1[key] == 1
+    assert CountingHash.calls == 2
+
+
+def test_lookups_follow_python_semantics():
+    class LargeHash:
+        def __hash__(self):
+            return 2 ** 100
+
+    class MinusOneHash:
+        def __hash__(self):
+            return -1
+
+    map_0 = pmap({1: "a", LargeHash(): "b", MinusOneHash(): "c"})
+
+    assert map_0[1.0] == "a"
+    assert map_0[True] == "a"
+    assert hash(map_0) == hash(frozenset(dict(map_0).items()))
+
+
+def test_unhashable_key_raises_type_error():
+    map_0 = pmap({"a": 1})
+
+    with pytest.raises(TypeError, match="unhashable type: 'list'"):
+        map_0.set([], 1)
+    with pytest.raises(TypeError, match="unhashable type: 'list'"):
+        map_0[[]]
+    with pytest.raises(TypeError, match="unhashable type: 'list'"):
+        [] in map_0
+    with pytest.raises(TypeError, match="unhashable type: 'list'"):
+        map_0.remove([])
+
+
 r"""
 The rest of the test suite was copied from
 https://github.com/tobgu/pyrsistent/blob/master/tests/map_test.py
@@ -134,15 +291,15 @@ def test_remove_non_existing_element_raises_key_error():
 def test_various_iterations():
     assert set(["a", "b"]) == set(m(a=1, b=2))
     assert ["a", "b"] == sorted(m(a=1, b=2).keys())
-    assert isinstance(m().keys(), PVector)
+    assert isinstance(m().keys(), PMapKeysView)
 
     assert set([1, 2]) == set(m(a=1, b=2).itervalues())
     assert [1, 2] == sorted(m(a=1, b=2).values())
-    assert isinstance(m().values(), PVector)
+    assert isinstance(m().values(), PMapValuesView)
 
     assert set([("a", 1), ("b", 2)]) == set(m(a=1, b=2).iteritems())
     assert set([("a", 1), ("b", 2)]) == set(m(a=1, b=2).items())
-    assert isinstance(m().items(), PVector)
+    assert isinstance(m().items(), PMapItemsView)
 
 
 def test_initialization_with_two_elements():
@@ -196,16 +353,12 @@ def test_overwrite_existing_element():
 
 
 def test_hash():
-    # pyrpds.pvector is order-agnostic
-    # x = m(a=1, b=2, c=3)
-    # y = m(a=1, b=2, c=3)
-    x = m(a=1)
-    y = m(a=1)
+    x = m(a=1, b=2, c=3)
+    y = m(a=1, b=2, c=3)
 
     assert hash(x) == hash(y)
 
 
-@pytest.mark.skip("pyrpds.pvector is order-agnostic right now")
 def test_same_hash_when_content_the_same_but_underlying_vector_size_differs():
     x = pmap(dict((x, x) for x in range(1000)))
     y = pmap({10: 10, 200: 200, 700: 700})
@@ -227,7 +380,6 @@ class HashabilityControlled(object):
         raise ValueError("I am not currently hashable.")
 
 
-@pytest.mark.skip("Currently, rust panics if an object is unhashable")
 def test_map_does_not_hash_values_on_second_hash_invocation():
     hashable = HashabilityControlled()
     x = pmap(dict(el=hashable))
@@ -259,10 +411,9 @@ def test_equal_to_dict():
     assert not (y != x)
 
 
-@pytest.mark.skip("Bucket size is not a supported argument right now!")
 def test_equal_with_different_bucket_sizes():
-    x = pmap({"a": 1, "b": 2}, 50)
-    y = pmap({"a": 1, "b": 2}, 10)
+    x = pmap({"a": 1, "b": 2}, degree=64)
+    y = pmap({"a": 1, "b": 2}, degree=8)
 
     assert x == y
     assert not (x != y)
@@ -271,10 +422,9 @@ def test_equal_with_different_bucket_sizes():
     assert not (y != x)
 
 
-@pytest.mark.skip("Bucket size is not a supported argument right now!")
 def test_equal_with_different_insertion_order():
-    x = pmap([(i, i) for i in range(50)], 10)
-    y = pmap([(i, i) for i in range(49, -1, -1)], 10)
+    x = pmap({i: i for i in range(50)}, degree=8)
+    y = pmap({i: i for i in range(49, -1, -1)}, degree=8)
 
     assert x == y
     assert not (x != y)
@@ -283,6 +433,23 @@ def test_equal_with_different_insertion_order():
     assert not (y != x)
 
 
+def test_degree_is_kept_by_derived_maps():
+    x = pmap(degree=2).update({i: i for i in range(100)})
+    y = pmap({i: i for i in range(100)})
+
+    assert x == y
+    assert hash(x) == hash(y)
+    assert x.remove(3) == y.remove(3)
+
+
+def test_invalid_degree_raises_value_error():
+    for degree in [0, 1, 3, 128]:
+        with pytest.raises(ValueError):
+            pmap({}, degree=degree)
+    with pytest.raises(ValueError):
+        pmap({}, 64)
+
+
 def test_not_equal():
     x = m(a=1, b=2, c=3)
     y = m(a=1, b=2)
@@ -551,12 +718,30 @@ def test_evolver_remove_element_not_present():
     assert str(error.value) == "'c'"
 
 
-@pytest.mark.skip("copy is not currently supported")
 def test_copy_returns_reference_to_self():
     m1 = m(a=10)
     assert m1.copy() is m1
 
 
+def test_copy_module_returns_reference_to_self():
+    import copy
+
+    m1 = m(a=10)
+    assert copy.copy(m1) is m1
+    assert copy.deepcopy(m1) is m1
+
+
+def test_deepcopy_copies_mutable_values():
+    import copy
+
+    m1 = m(a=[1], b=2)
+    m2 = copy.deepcopy(m1)
+
+    assert m2 == m1
+    assert m2["a"] is not m1["a"]
+    assert m2["b"] is m1["b"]
+
+
 def test_dot_access_of_non_existing_element_raises_attribute_error():
     m1 = m(a=10)
 
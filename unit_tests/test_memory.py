@@ -0,0 +1,45 @@
+import sys
+import pytest
+
+from pyrpds import pvector, plist, pmap, pset, pvector_i64, memory_usage
+
+
+def test_sizeof_counts_the_elements():
+    for constructor in [pvector, plist, pset, pvector_i64]:
+        empty = constructor()
+        small = constructor(range(10))
+        large = constructor(range(1000))
+
+        assert sys.getsizeof(empty) < sys.getsizeof(small) < sys.getsizeof(large)
+
+    assert sys.getsizeof(pmap()) < sys.getsizeof(pmap({i: i for i in range(1000)}))
+
+
+def test_memory_usage_of_a_single_container_is_its_size():
+    container = pmap({i: str(i) for i in range(100)})
+
+    assert memory_usage(container) == container.__sizeof__()
+    assert memory_usage(container, container) == container.__sizeof__()
+    assert memory_usage() == 0
+
+
+def test_memory_usage_counts_shared_elements_once():
+    vector_0 = pvector(range(1000))
+    vector_1 = vector_0.append(1000)
+    vector_2 = pvector(range(1001))
+
+    shared = memory_usage(vector_0, vector_1)
+    assert shared < vector_0.__sizeof__() + vector_1.__sizeof__()
+    assert shared < memory_usage(vector_0, vector_2)
+
+    map_0 = pmap({i: i for i in range(1000)})
+    map_1 = map_0.set(0, "0")
+    assert memory_usage(map_0, map_1) < map_0.__sizeof__() + map_1.__sizeof__()
+
+    set_0 = pset(range(1000))
+    assert memory_usage(set_0, set_0.add(1000), set_0.remove(0)) < 2 * set_0.__sizeof__()
+
+
+def test_memory_usage_rejects_other_objects():
+    with pytest.raises(TypeError):
+        memory_usage(pvector(), [1, 2])
@@ -0,0 +1,164 @@
+import array
+import copy
+import struct
+from concurrent.futures import ThreadPoolExecutor
+import pytest
+
+from pyrpds import pvector, pvector_i64, pvector_f64, pvector_bool
+
+
+def test_i64_vector():
+    vector_0 = pvector_i64()
+    assert len(vector_0) == 0
+    assert list(vector_0) == []
+
+    vector_1 = vector_0.append(1).extend(range(2, 5))
+    assert list(vector_1) == [1, 2, 3, 4]
+    assert vector_1[0] == 1
+    assert vector_1[-1] == 4
+    assert vector_1.get(-2) == 3
+    assert vector_1.set(0, 10)[0] == 10
+    assert vector_1.set(4, 5)[4] == 5
+    assert vector_1.mset(0, 7, 1, 8) == pvector_i64([7, 8, 3, 4])
+    assert vector_1.remove(2) == pvector_i64([1, 3, 4])
+    assert vector_1.delete(1, 3) == pvector_i64([1, 4])
+    assert vector_1.delete(-1) == pvector_i64([1, 2, 3])
+    assert list(reversed(vector_1)) == [4, 3, 2, 1]
+    assert list(vector_0) == []
+
+    with pytest.raises(IndexError):
+        vector_1[4]
+    with pytest.raises(IndexError):
+        vector_1.set(6, 1)
+
+
+def test_elements_are_converted_at_the_boundary():
+    assert type(pvector_i64([1])[0]) is int
+    assert type(pvector_f64([1])[0]) is float
+    assert type(pvector_bool([1 == 1])[0]) is bool
+
+    with pytest.raises(TypeError):
+        pvector_i64(["a"])
+    with pytest.raises(OverflowError):
+        pvector_i64([2 ** 64])
+    with pytest.raises(TypeError):
+        pvector_f64([1.0]).append("a")
+
+
+def test_search():
+    vector = pvector_f64([1.0, 2.5, 1.0, -0.0])
+
+    assert 2.5 in vector
+    assert 1 in vector
+    assert "a" not in vector
+    assert vector.index(1.0) == 0
+    assert vector.index(1.0, 1) == 2
+    assert vector.index(0.0) == 3
+    assert vector.count(1.0) == 2
+    assert vector.count(1.0, 1, 2) == 0
+    assert vector.count("a") == 0
+
+    with pytest.raises(ValueError):
+        vector.index(3.0)
+    with pytest.raises(ValueError):
+        vector.remove("a")
+
+
+def test_search_compares_other_types_with_eq():
+    vector = pvector_i64([1, 2, 2])
+
+    assert 2.0 in vector
+    assert 2.5 not in vector
+    assert 2 ** 64 not in vector
+    assert vector.count(2.0) == 2
+    assert vector.index(2.0) == 1
+    assert vector.remove(2.0) == pvector_i64([1, 2])
+
+    assert 1 in pvector_bool([True])
+    assert pvector_bool([False, True]).index(1) == 1
+    assert pvector_f64([2.0 ** 64]).count(2 ** 64) == 1
+
+    class Unequal:
+        def __eq__(self, other):
+            raise RuntimeError()
+
+    with pytest.raises(RuntimeError):
+        Unequal() in vector
+
+
+def test_sum():
+    assert pvector_i64(range(100)).sum() == sum(range(100))
+    assert pvector_f64([0.5, 0.25]).sum() == 0.75
+    assert pvector_bool([True, False, True]).sum() == 2
+    assert pvector_i64().sum() == 0
+
+    assert pvector_i64([2 ** 62, 2 ** 62]).sum() == 2 ** 63
+    assert pvector_i64([-(2 ** 63)] * 3).sum() == -3 * 2 ** 63
+
+
+def test_equality_and_hash():
+    assert pvector_i64([1, 2]) == pvector_i64([1, 2])
+    assert pvector_i64([1, 2]) != pvector_i64([2, 1])
+    assert pvector_i64([1]) != pvector([1])
+    assert hash(pvector_i64([1, 2])) == hash(pvector_i64([1, 2]))
+    assert hash(pvector_f64([0.0])) == hash(pvector_f64([-0.0]))
+    assert pvector_f64([float("nan")]) != pvector_f64([float("nan")])
+
+
+def test_repr():
+    assert repr(pvector_i64([1, 2])) == "pvector_i64([1, 2])"
+    assert repr(pvector_f64([1, 1e20])) == "pvector_f64([1.0, 1e+20])"
+    assert repr(pvector_bool([True])) == "pvector_bool([True])"
+    assert repr(pvector_bool()) == "pvector_bool([])"
+
+
+def test_copy_returns_self():
+    vector = pvector_i64([1, 2])
+
+    assert copy.copy(vector) is vector
+    assert copy.deepcopy(vector) is vector
+
+
+def test_to_buffer():
+    buffer = pvector_f64([0.5, 1.5, 2.5]).to_buffer()
+
+    assert buffer.format == "d"
+    assert buffer.tolist() == [0.5, 1.5, 2.5]
+    assert struct.unpack("3d", buffer) == (0.5, 1.5, 2.5)
+    assert pvector_i64([1, -2]).to_buffer().tolist() == [1, -2]
+    assert pvector_bool([True, False]).to_buffer().tolist() == [True, False]
+    assert len(pvector_i64().to_buffer()) == 0
+
+
+def test_from_buffer():
+    vector = pvector_i64([1, -2, 3])
+
+    assert type(vector).from_buffer(vector.to_buffer()) == vector
+    assert type(vector).from_buffer(bytes(vector.to_buffer())) == vector
+    assert type(vector).from_buffer(array.array("q", [1, -2, 3])) == vector
+    assert pvector_f64().from_buffer(array.array("d", [0.5])) == pvector_f64([0.5])
+    assert pvector_bool().from_buffer(b"\x01\x00") == pvector_bool([True, False])
+
+    with pytest.raises(TypeError):
+        type(vector).from_buffer(array.array("d", [1.0]))
+    with pytest.raises(ValueError):
+        type(vector).from_buffer(b"\x00" * 7)
+    with pytest.raises(TypeError):
+        type(vector).from_buffer([1, 2])
+
+
+def test_used_from_several_threads():
+    vector = pvector_i64(range(10000))
+
+    def work(offset):
+        spliced = vector.delete(offset, offset + 100)
+        return spliced.sum(), spliced.index(offset + 100), vector.count(offset), hash(spliced)
+
+    with ThreadPoolExecutor(max_workers=4) as executor:
+        results = list(executor.map(work, range(0, 1000, 100)))
+
+    for offset, (total, index, count, spliced_hash) in zip(range(0, 1000, 100), results):
+        assert total == sum(range(10000)) - sum(range(offset, offset + 100))
+        assert index == offset
+        assert count == 1
+        assert spliced_hash == hash(vector.delete(offset, offset + 100))
@@ -0,0 +1,148 @@
+import copy
+import random
+import pytest
+
+from pyrpds import memory_usage, pvector, pvector_rrb
+from pyrpds.debug import shares_structure
+
+
+def test_rrb_vector():
+    vector_0 = pvector_rrb()
+    assert len(vector_0) == 0
+    assert list(vector_0) == []
+
+    vector_1 = vector_0.append(1).extend(range(2, 5))
+    assert list(vector_1) == [1, 2, 3, 4]
+    assert vector_1[0] == 1
+    assert vector_1[-1] == 4
+    assert vector_1.get(-2) == 3
+    assert vector_1.set(0, 10)[0] == 10
+    assert vector_1.set(4, 5)[4] == 5
+    assert vector_1.insert(2, "a") == pvector_rrb([1, 2, "a", 3, 4])
+    assert vector_1.insert(-1, "a") == pvector_rrb([1, 2, 3, "a", 4])
+    assert vector_1.insert(100, "a") == pvector_rrb([1, 2, 3, 4, "a"])
+    assert vector_1.remove(2) == pvector_rrb([1, 3, 4])
+    assert vector_1.delete(1, 3) == pvector_rrb([1, 4])
+    assert vector_1.delete(-1) == pvector_rrb([1, 2, 3])
+    assert vector_1.index(3) == 2
+    assert vector_1.count(3) == 1
+    assert 4 in vector_1
+    assert list(reversed(vector_1)) == [4, 3, 2, 1]
+    assert list(vector_0) == []
+
+    with pytest.raises(IndexError):
+        vector_1[4]
+    with pytest.raises(IndexError):
+        vector_1.set(6, 1)
+    with pytest.raises(ValueError):
+        vector_1.remove(5)
+
+
+def test_concatenation():
+    x = pvector_rrb(range(1000))
+    y = pvector_rrb(range(1000, 1500))
+
+    assert list(x + y) == list(range(1500))
+    assert list(x + [1, 2]) == list(range(1000)) + [1, 2]
+    assert x + pvector_rrb() == x
+    assert pvector_rrb() + y == y
+
+    with pytest.raises(TypeError):
+        [1, 2] + x
+
+
+def test_slicing():
+    x = pvector_rrb(range(1000))
+
+    assert x[100:200] == pvector_rrb(range(100, 200))
+    assert x[-10:] == pvector_rrb(range(990, 1000))
+    assert x[::7] == pvector_rrb(range(0, 1000, 7))
+    assert x[::-1] == pvector_rrb(range(999, -1, -1))
+    assert x[500:100] == pvector_rrb()
+    assert x[:] == x
+
+
+def test_matches_list_under_random_splices():
+    random.seed(0)
+    model = list(range(100))
+    vector = pvector_rrb(model)
+
+    for step in range(1000):
+        index = random.randint(0, len(model))
+        operation = random.randrange(4)
+        if operation == 0:
+            vector = vector.insert(index, step)
+            model.insert(index, step)
+        elif operation == 1 and index < len(model):
+            vector = vector.delete(index)
+            del model[index]
+        elif operation == 2:
+            other = list(range(random.randrange(100)))
+            vector = vector[:index] + pvector_rrb(other) + vector[index:]
+            model = model[:index] + other + model[index:]
+        else:
+            stop = random.randint(index, len(model))
+            vector = vector.delete(index, stop)
+            del model[index:stop]
+
+        assert len(vector) == len(model)
+
+    assert list(vector) == model
+    assert [vector[index] for index in range(len(model))] == model
+
+
+def test_search_from_an_offset():
+    model = list(range(100)) + list(range(1000)) + list(range(50))
+    vector = pvector_rrb(range(100)) + pvector_rrb(range(1000)) + pvector_rrb(range(50))
+
+    for start in range(0, len(model), 7):
+        assert vector.count(5, start) == model[start:].count(5)
+        assert vector.count(5, start, start + 40) == model[start : start + 40].count(5)
+    assert vector.index(5, 6) == model.index(5, 6)
+    assert vector.index(5, 106) == model.index(5, 106)
+    assert vector.count(5, 500, 100) == 0
+
+    with pytest.raises(ValueError):
+        vector.index(999, 1100)
+
+
+def test_equality_and_hash():
+    x = pvector_rrb(range(100))
+    y = pvector_rrb(range(50)) + pvector_rrb(range(50, 100))
+
+    assert x == y
+    assert hash(x) == hash(y)
+    assert x != y.set(0, -1)
+    assert x != pvector(range(100))
+
+
+def test_repr():
+    assert repr(pvector_rrb()) == "pvector_rrb([])"
+    assert repr(pvector_rrb([1, "a"])) == "pvector_rrb([1, 'a'])"
+
+
+def test_copy():
+    vector = pvector_rrb([1, [2]])
+
+    assert copy.copy(vector) is vector
+    deep_copy = copy.deepcopy(vector)
+    assert deep_copy == vector
+    assert deep_copy[1] is not vector[1]
+    assert copy.deepcopy(pvector_rrb([1, 2])) == pvector_rrb([1, 2])
+
+
+def test_splices_keep_the_rest_shared():
+    x = pvector_rrb(range(10000))
+
+    assert shares_structure(x, x.insert(5000, -1))
+    assert shares_structure(x, x[:5000])
+    assert shares_structure(x, x + x)
+
+
+def test_memory_counts_the_nodes():
+    # A 33rd element needs a second leaf and a branch above both leaves.
+    assert pvector_rrb(range(33)).__sizeof__() > 2 * pvector_rrb(range(32)).__sizeof__()
+
+    x = pvector_rrb(range(1000))
+    assert memory_usage(x, x + x) < x.__sizeof__() + (x + x).__sizeof__()
+    assert memory_usage(x, x.set(0, -1)) < 2 * x.__sizeof__()
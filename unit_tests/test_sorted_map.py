@@ -0,0 +1,162 @@
+import copy
+
+import pytest
+
+from pyrpds import pmap, psortedmap
+
+
+def test_keys_are_sorted():
+    map_0 = psortedmap({3: "c", 1: "a", 2: "b"})
+    assert list(map_0) == [1, 2, 3]
+    assert list(map_0.keys()) == [1, 2, 3]
+    assert list(map_0.values()) == ["a", "b", "c"]
+    assert list(map_0.items()) == [(1, "a"), (2, "b"), (3, "c")]
+    assert list(reversed(map_0)) == [3, 2, 1]
+
+
+def test_constructors():
+    assert psortedmap() == psortedmap({})
+    assert psortedmap({2: "b", 1: "a"}) == psortedmap({1: "a", 2: "b"})
+    assert psortedmap(pmap({1: "a"})) == psortedmap({1: "a"})
+    assert psortedmap().update({1: "a"}, pmap({2: "b"})) == psortedmap({1: "a", 2: "b"})
+
+
+def test_set_and_remove_leave_the_original_unchanged():
+    map_0 = psortedmap({1: "a"})
+    map_1 = map_0.set(0, "z").set(1, "b")
+    assert list(map_1.items()) == [(0, "z"), (1, "b")]
+    assert list(map_0.items()) == [(1, "a")]
+
+    assert map_1.discard(0) == psortedmap({1: "b"})
+    assert map_1.discard(5) == map_1
+    assert map_1.remove(1) == psortedmap({0: "z"})
+    with pytest.raises(KeyError):
+        map_1.remove(5)
+
+
+def test_lookup():
+    map_0 = psortedmap({"a": 1, "b": 2})
+    assert map_0["a"] == 1
+    assert map_0.get("b") == 2
+    assert "a" in map_0
+    assert "c" not in map_0
+    assert len(map_0) == 2
+    with pytest.raises(KeyError):
+        map_0["c"]
+
+
+def test_first_last_and_pop_first():
+    map_0 = psortedmap({2: "b", 1: "a", 3: "c"})
+    assert map_0.first() == (1, "a")
+    assert map_0.last() == (3, "c")
+
+    item, rest = map_0.pop_first()
+    assert item == (1, "a")
+    assert rest == psortedmap({2: "b", 3: "c"})
+    assert len(map_0) == 3
+
+    for operation in (psortedmap().first, psortedmap().last, psortedmap().pop_first):
+        with pytest.raises(KeyError):
+            operation()
+
+
+def test_floor_and_ceiling():
+    map_0 = psortedmap({10: "a", 20: "b", 30: "c"})
+    assert map_0.floor(20) == (20, "b")
+    assert map_0.floor(25) == (20, "b")
+    assert map_0.floor(5) is None
+    assert map_0.ceiling(20) == (20, "b")
+    assert map_0.ceiling(25) == (30, "c")
+    assert map_0.ceiling(35) is None
+
+
+def test_range():
+    map_0 = psortedmap({i: str(i) for i in range(10)})
+    assert [key for key, _ in map_0.range(3, 6)] == [3, 4, 5]
+    assert [key for key, _ in map_0.range(3, 6, inclusive=(False, True))] == [4, 5, 6]
+    assert [key for key, _ in map_0.range(3, 6, (True, True))] == [3, 4, 5, 6]
+    assert [key for key, _ in map_0.range(high=2)] == [0, 1]
+    assert [key for key, _ in map_0.range(low=8)] == [8, 9]
+    assert len(list(map_0.range())) == 10
+
+    assert list(map_0.range(6, 3)) == []
+    assert list(map_0.range(3, 3)) == []
+    assert list(map_0.range(3, 3, (False, False))) == []
+    assert list(map_0.range(3, 3, (True, True))) == [(3, "3")]
+    assert list(map_0.range(6, 3, (True, True))) == []
+    assert list(map_0.range(6, 3, (False, False))) == []
+
+
+def test_key_function():
+    map_0 = psortedmap({"b": 1, "A": 2, "c": 3}, key=str.lower)
+    assert list(map_0) == ["A", "b", "c"]
+    assert map_0["A"] == 2
+    assert "a" not in map_0
+    assert list(map_0.set("b", 4).items()) == [("A", 2), ("b", 4), ("c", 3)]
+    assert map_0.floor("bb") == ("b", 1)
+
+    by_length = psortedmap({"ccc": 3, "a": 1}, key=len)
+    assert list(by_length) == ["a", "ccc"]
+    assert repr(by_length).startswith("psortedmap({'a': 1, 'ccc': 3}, key=")
+
+
+def test_key_function_only_orders_the_keys():
+    assert psortedmap({-1: "a"}, key=abs) != psortedmap({1: "a"})
+    assert psortedmap({-1: "a"}, key=abs) == psortedmap({-1: "a"})
+    assert psortedmap({-1: "a"}, key=abs) == psortedmap({-1: "a"}, key=lambda key: -key)
+
+    # Distinct keys with equivalent sort keys are kept, in the order they were inserted.
+    map_0 = psortedmap({-1: "a", 1: "b", 0: "c"}, key=abs)
+    assert len(map_0) == 3
+    assert list(map_0.items()) == [(0, "c"), (-1, "a"), (1, "b")]
+    assert map_0[-1] == "a" and map_0[1] == "b"
+    assert map_0.discard(-1) == psortedmap({0: "c", 1: "b"})
+    assert list(map_0.range(1, 2)) == [(-1, "a"), (1, "b")]
+    assert map_0.last() == (1, "b")
+
+    # The sort keys don't need to be hashable, the keys do.
+    by_list = psortedmap({1: "a", 2: "b"}, key=lambda key: [key])
+    assert hash(by_list) == hash(psortedmap({2: "b", 1: "a"}))
+
+
+def test_incomparable_keys_raise_type_error():
+    map_0 = psortedmap({1: "a"})
+    with pytest.raises(TypeError):
+        map_0.set("b", 2)
+    with pytest.raises(TypeError):
+        "b" in map_0
+    with pytest.raises(TypeError):
+        list(map_0.range("a", "b"))
+    assert map_0 == psortedmap({1: "a"})
+
+
+def test_repr():
+    assert repr(psortedmap()) == "psortedmap({})"
+    assert repr(psortedmap({2: "b", 1: "a"})) == "psortedmap({1: 'a', 2: 'b'})"
+
+
+def test_equality_and_hash():
+    map_0 = psortedmap({1: "a", 2: "b"})
+    map_1 = psortedmap({2: "b", 1: "a"})
+    assert map_0 == map_1
+    assert hash(map_0) == hash(map_1)
+    assert map_0 != map_0.set(2, "c")
+    assert map_0 != pmap({1: "a", 2: "b"})
+    with pytest.raises(TypeError):
+        hash(psortedmap({1: []}))
+
+
+def test_copy_and_deepcopy():
+    map_0 = psortedmap({1: [1], 2: [2]})
+    assert copy.copy(map_0) is map_0
+
+    map_1 = copy.deepcopy(map_0)
+    assert map_1 == map_0
+    assert map_1[1] is not map_0[1]
+
+    immutable = psortedmap({1: "a"})
+    assert copy.deepcopy(immutable) is immutable
+
+
+def test_sizeof():
+    assert psortedmap({i: i for i in range(100)}).__sizeof__() > psortedmap().__sizeof__()
@@ -0,0 +1,173 @@
+import copy
+
+import pytest
+
+from pyrpds import pset, psortedset
+
+
+def test_elements_are_sorted():
+    set_0 = psortedset([3, 1, 2, 3])
+    assert list(set_0) == [1, 2, 3]
+    assert list(reversed(set_0)) == [3, 2, 1]
+    assert len(set_0) == 3
+    assert 2 in set_0
+    assert 4 not in set_0
+
+
+def test_add_and_remove_leave_the_original_unchanged():
+    set_0 = psortedset([2])
+    set_1 = set_0.add(1).add(3)
+    assert list(set_1) == [1, 2, 3]
+    assert list(set_0) == [2]
+
+    assert list(set_1.discard(2)) == [1, 3]
+    assert set_1.discard(5) == set_1
+    assert list(set_1.remove(1)) == [2, 3]
+    with pytest.raises(KeyError):
+        set_1.remove(5)
+
+    assert list(set_0.update([0, 4])) == [0, 2, 4]
+
+
+def test_first_and_last():
+    set_0 = psortedset([5, 3, 9])
+    assert set_0.first() == 3
+    assert set_0.last() == 9
+    with pytest.raises(KeyError):
+        psortedset().first()
+    with pytest.raises(KeyError):
+        psortedset().last()
+
+
+def test_neighbors():
+    set_0 = psortedset([10, 20, 30])
+    assert set_0.floor(20) == 20
+    assert set_0.floor(25) == 20
+    assert set_0.floor(5) is None
+    assert set_0.ceiling(20) == 20
+    assert set_0.ceiling(25) == 30
+    assert set_0.ceiling(35) is None
+    assert set_0.lower(20) == 10
+    assert set_0.lower(10) is None
+    assert set_0.higher(20) == 30
+    assert set_0.higher(30) is None
+
+
+def test_rank_and_index_of():
+    set_0 = psortedset([10, 20, 30])
+    assert set_0.rank(5) == 0
+    assert set_0.rank(20) == 1
+    assert set_0.rank(25) == 2
+    assert set_0.rank(35) == 3
+
+    assert set_0.index_of(10) == 0
+    assert set_0.index_of(30) == 2
+    with pytest.raises(ValueError):
+        set_0.index_of(25)
+
+
+def test_range():
+    set_0 = psortedset(range(10))
+    assert list(set_0.range(3, 6)) == [3, 4, 5]
+    assert list(set_0.range(3, 6, inclusive=(False, True))) == [4, 5, 6]
+    assert list(set_0.range(high=2)) == [0, 1]
+    assert list(set_0.range(low=8)) == [8, 9]
+    assert list(set_0.range(6, 3)) == []
+    assert list(set_0.range(3, 3, (False, False))) == []
+    assert list(set_0.range(3, 3, (True, True))) == [3]
+    assert list(set_0.range(6, 3, (True, True))) == []
+    assert list(set_0.range(6, 3, (False, False))) == []
+
+
+def test_set_algebra_keeps_the_result_sorted():
+    set_0 = psortedset([1, 2, 3, 4])
+    set_1 = psortedset([3, 4, 5, 6])
+
+    assert list(set_0 | set_1) == [1, 2, 3, 4, 5, 6]
+    assert list(set_0 & set_1) == [3, 4]
+    assert list(set_0 - set_1) == [1, 2]
+    assert list(set_0 ^ set_1) == [1, 2, 5, 6]
+
+    assert list(set_0.union([0, 9])) == [0, 1, 2, 3, 4, 9]
+    assert list(set_0.intersection(pset([2, 4, 8]))) == [2, 4]
+    assert list(set_0.difference(range(3))) == [3, 4]
+    assert list(set_0.symmetric_difference([4, 5])) == [1, 2, 3, 5]
+
+    with pytest.raises(TypeError):
+        set_0 | pset([1])
+
+
+def test_key_function():
+    set_0 = psortedset(["b", "A", "c"], key=str.lower)
+    assert list(set_0) == ["A", "b", "c"]
+    assert "A" in set_0
+    assert "a" not in set_0
+    assert set_0.rank("B") == 1
+    assert repr(set_0).startswith("psortedset(['A', 'b', 'c'], key=")
+
+    # The elements are ordered by the key function of the left operand.
+    by_length = psortedset(["ccc", "a"], key=len)
+    assert list(set_0 | by_length) == ["A", "a", "b", "c", "ccc"]
+    assert list(by_length.union(["bb"])) == ["a", "bb", "ccc"]
+
+
+def test_key_function_only_orders_the_elements():
+    assert psortedset([-1], key=abs) != psortedset([1])
+    assert psortedset([-1], key=abs) == psortedset([-1], key=lambda element: -element)
+
+    # Distinct elements with equivalent sort keys are kept, in the order they were inserted.
+    set_0 = psortedset([-1, 1, 0], key=abs)
+    assert len(psortedset([-1, 1], key=abs)) == 2
+    assert list(set_0) == [0, -1, 1]
+    assert 1 in set_0 and -1 in set_0
+    assert list(set_0.discard(-1)) == [0, 1]
+    assert list(set_0.range(1, 2)) == [-1, 1]
+    assert set_0.floor(1) == 1
+    assert set_0.lower(1) == 0
+    assert set_0.rank(1) == 1
+    assert set_0.index_of(1) == 2
+    assert list(set_0 & psortedset([1, 2], key=abs)) == [1]
+    assert list(set_0 - psortedset([1], key=abs)) == [0, -1]
+
+    # The sort keys don't need to be hashable, the elements do.
+    by_list = psortedset([1, 2], key=lambda element: [element])
+    assert hash(by_list) == hash(frozenset([1, 2]))
+
+
+def test_incomparable_elements_raise_type_error():
+    set_0 = psortedset([1])
+    with pytest.raises(TypeError):
+        set_0.add("a")
+    with pytest.raises(TypeError):
+        set_0.floor("a")
+    assert set_0 == psortedset([1])
+
+
+def test_repr():
+    assert repr(psortedset()) == "psortedset([])"
+    assert repr(psortedset([2, 1])) == "psortedset([1, 2])"
+
+
+def test_equality_and_hash():
+    set_0 = psortedset([1, 2, 3])
+    set_1 = psortedset([3, 2, 1])
+    assert set_0 == set_1
+    assert hash(set_0) == hash(set_1)
+    assert hash(set_0) == hash(frozenset([1, 2, 3]))
+    assert set_0 != set_0.add(4)
+    assert set_0 != pset([1, 2, 3])
+
+
+def test_copy_and_deepcopy():
+    set_0 = psortedset([(1, 2), (3, 4)])
+    assert copy.copy(set_0) is set_0
+    assert copy.deepcopy(set_0) is set_0
+
+    lists = psortedset([[1], [2]])
+    copied = copy.deepcopy(lists)
+    assert list(copied) == [[1], [2]]
+    assert next(iter(copied)) is not next(iter(lists))
+
+
+def test_sizeof():
+    assert psortedset(range(100)).__sizeof__() > psortedset().__sizeof__()
@@ -107,6 +107,144 @@ def test_iter():
         assert index == element
 
 
+def test_iter_is_lazy():
+    container = pvector(range(100))
+    iterator = iter(container)
+    del container
+
+    assert next(iterator) == 0
+    assert list(iterator) == list(range(1, 100))
+    assert list(iterator) == []
+
+
+def test_reversed():
+    assert list(reversed(pvector())) == []
+    assert list(reversed(pvector(range(100)))) == list(reversed(range(100)))
+
+
+def test_index_and_count_within_limits():
+    x = pvector([1, 2, 5, 1])
+
+    assert x.index(1, 1) == 3
+    assert x.index(1, -1) == 3
+    assert x.index(2, -10, 100) == 1
+    assert x.count(1, 1) == 1
+    assert x.count(1, 0, -1) == 1
+    assert x.count(1, stop=10) == 2
+    assert x.count(1, 3, 1) == 0
+
+
+def test_contains_compares_identity_first():
+    nan = float("nan")
+    x = pvector([1.0, nan])
+
+    assert nan in x
+    assert float("nan") not in x
+    assert x.index(nan) == 1
+    assert x.count(nan) == 1
+
+
+def test_stores_unhashable_elements():
+    x = pvector([[1], {"a": 2}])
+
+    assert x == pvector([[1], {"a": 2}])
+    assert x.index([1]) == 0
+    assert x.set(0, [3])[0] == [3]
+
+    with pytest.raises(TypeError, match="unhashable type: 'list'"):
+        hash(x)
+
+    assert hash(x.set(0, 1).set(1, 2)) == hash(pvector([1, 2]))
+
+
+def test_builds_from_any_iterable():
+    x = pvector(i for i in range(1000))
+
+    assert len(x) == 1000
+    assert list(x) == list(range(1000))
+    assert x.extend(iter([1000, 1001]))[-1] == 1001
+    assert list(v(*range(3))) == [0, 1, 2]
+
+    with pytest.raises(TypeError):
+        pvector(1)
+
+
+def test_equal_with_different_branching_factors():
+    x = pvector(range(1000), branching_factor=2)
+    y = pvector(range(1000), branching_factor=256)
+
+    assert x == y
+    assert hash(x) == hash(y)
+    assert x.append(1000).set(5, -1) == y.append(1000).set(5, -1)
+    assert x[999] == 999
+
+    for branching_factor in [0, 1, 12, 512]:
+        with pytest.raises(ValueError):
+            pvector([], branching_factor=branching_factor)
+
+    # The tuning arguments are keyword-only, a second positional argument is still an error.
+    with pytest.raises(ValueError):
+        pvector([], 32)
+    with pytest.raises(TypeError):
+        pvector([], degree=32)
+
+
+def test_remove_and_delete_keep_the_original_intact():
+    x = pvector(range(2000))
+
+    assert list(x.remove(1000)) == list(range(1000)) + list(range(1001, 2000))
+    assert list(x.delete(0, 1990)) == list(range(1990, 2000))
+    assert list(x.delete(-1)) == list(range(1999))
+    assert list(x) == list(range(2000))
+
+
+def test_equality_follows_python_semantics():
+    class EqualToEverything:
+        def __eq__(self, other):
+            return True
+
+    class NoOpinion:
+        def __eq__(self, other):
+            return NotImplemented
+
+    assert 1.0 in pvector([1])
+    assert pvector([1, 2.0]) == pvector([1.0, 2])
+    assert pvector([1]).index(True) == 0
+    assert EqualToEverything() in pvector([1])
+    assert pvector([NoOpinion()]).count(NoOpinion()) == 0
+
+
+def test_hash_is_computed_once():
+    class CountingHash:
+        calls = 0
+
+        def __hash__(self):
+            CountingHash.calls += 1
+            return 1
+
+    x = pvector([CountingHash()])
+
+    assert hash(x) == hash(x)
+    assert CountingHash.calls == 1
+
+
+def test_failing_eq_raises_original_error():
+    class Unequal:
+        def __eq__(self, other):
+            raise ValueError("cannot compare")
+
+    x = pvector([Unequal()])
+
+    with pytest.raises(ValueError):
+        1 in x
+    with pytest.raises(ValueError):
+        x.index(1)
+    with pytest.raises(ValueError):
+        x.remove(1)
+    with pytest.raises(ValueError):
+        x == pvector([1])
+
+
 r"""
 The rest of the test suite was copied from
 https://github.com/tobgu/pyrsistent/blob/master/tests/vector_test.py
@@ -339,7 +477,6 @@ def test_slicing_reverse():
     assert len(seq3) == 4
 
 
-@pytest.mark.skip("delete method is not yet implemented")
 def test_delete_index():
     seq = pvector([1, 2, 3])
     assert seq.delete(0) == pvector([2, 3])
@@ -350,7 +487,6 @@ def test_delete_index():
     assert seq.delete(-3) == pvector([2, 3])
 
 
-@pytest.mark.skip("delete method is not yet implemented")
 def test_delete_index_out_of_bounds():
     with pytest.raises(IndexError):
         pvector([]).delete(0)
@@ -358,13 +494,11 @@ def test_delete_index_out_of_bounds():
         pvector([]).delete(-1)
 
 
-@pytest.mark.skip("delete method is not yet implemented")
 def test_delete_index_malformed():
     with pytest.raises(TypeError):
         pvector([]).delete("a")
 
 
-@pytest.mark.skip("delete method is not yet implemented")
 def test_delete_slice():
     seq = pvector(range(5))
     assert seq.delete(1, 4) == pvector([0, 4])
@@ -460,7 +594,7 @@ def test_repr_when_contained_object_contains_reference_to_self():
     x = [1, 2, 3]
     v = pvector([1, 2, x])
     x.append(v)
-    assert str(v) == "pvector([1, 2, [1, 2, 3, pvector([1, 2, [...]])]])"
+    assert str(v) == "pvector([1, 2, [1, 2, 3, ...]])"
 
     # Run a GC to provoke any potential misbehavior
     import gc
@@ -468,6 +602,25 @@ def test_repr_when_contained_object_contains_reference_to_self():
     gc.collect()
 
 
+def test_repr_when_contained_object_without_guard_contains_reference_to_self():
+    class A:
+        def __repr__(self):
+            return "A(%r)" % (self.v,)
+
+    a = A()
+    a.v = pvector([a])
+    assert repr(a.v) == "pvector([A(...)])"
+
+
+def test_repr_raises_when_element_repr_returns_non_string():
+    class A:
+        def __repr__(self):
+            return 1
+
+    with pytest.raises(TypeError):
+        repr(pvector([A()]))
+
+
 def test_is_hashable():
 
     v = pvector([1, 2, 3])
@@ -476,7 +629,6 @@ def test_is_hashable():
     assert hash(v) == hash(v2)
 
 
-@pytest.mark.skip("Currently, rust panics if an object is unhashable")
 def test_refuses_to_hash_when_members_are_unhashable():
     v = pvector([1, 2, [1, 2]])
 
@@ -616,7 +768,6 @@ def test_index_not_found():
         x.index(7)
 
 
-@pytest.mark.skip("index method currently does not support search within limits")
 def test_index_not_found_with_limits():
     x = pvector([1, 2, 5, 1])
 
@@ -1069,6 +1220,26 @@ def test_python_no_c_extension_with_environment_variable():
     reload_module(pyrsistent)
 
 
+def test_copy_returns_reference_to_self():
+    import copy
+
+    v1 = pvector([1, "a"])
+    assert copy.copy(v1) is v1
+    assert copy.deepcopy(v1) is v1
+
+
+def test_deepcopy_copies_mutable_elements():
+    import copy
+
+    v1 = pvector([1, [2], (3,)])
+    v2 = copy.deepcopy(v1)
+
+    assert v2 == v1
+    assert v2[0] is v1[0]
+    assert v2[1] is not v1[1]
+    assert v2[2] is v1[2]
+
+
 @pytest.mark.skip("weakref is not currently supported")
 def test_supports_weakref():
     import weakref
@@ -1090,7 +1261,6 @@ def test_get_evolver_referents():
     assert len([x for x in gc.get_referents(e) if x is v]) == 1
 
 
-@pytest.mark.skip("not supported currently")
 def test_failing_repr():
     # See https://github.com/tobgu/pyrsistent/issues/84
     class A(object):